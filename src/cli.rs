@@ -0,0 +1,294 @@
+use crate::dataprep::attribution::{attribute_speakers, speaker_stats};
+use crate::dataprep::detection::read_subtitle_file;
+use crate::dataprep::glossary::harvest_furigana;
+use crate::dataprep::kanji::KanjiReport;
+use crate::dataprep::modelling::CharacterTable;
+use crate::dataprep::normalisation::NormalisationReport;
+use crate::dataprep::parser::SubtitleParser;
+use crate::dataprep::processing::EpisodeBreakdown;
+use crate::dataprep::retiming::{Anchor, OutOfRangePolicy, convert_framerate, resync, shift};
+use crate::dataprep::writer::SrtWriter;
+use crate::types::framerate::Framerate;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::Timestamp;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+const USAGE: &str = "\
+Usage:
+  examining-one-anime-episodes-subtitles
+      Parses the bundled Psycho-Pass episode and prints every unit.
+
+  examining-one-anime-episodes-subtitles shift <input.srt> <offset> [options]
+      Moves every unit by <offset>: signed milliseconds (-1500) or a signed
+      timestamp (-00:00:01,500).
+
+  examining-one-anime-episodes-subtitles resync <input.srt> <sub>=<actual> <sub>=<actual> [options]
+      Maps subtitle times onto actual times through two anchors, e.g.
+      00:01:00,000=00:01:02,300 00:20:00,000=00:20:04,100
+
+  examining-one-anime-episodes-subtitles framerate <input.srt> <from> <to> [options]
+      Rescales every unit for a video played at a different framerate, e.g.
+      23.976 25 for a PAL speed-up. Rates may also be fractions (24000/1001).
+
+  examining-one-anime-episodes-subtitles speakers <input>...
+      Credits each line to the last labelled speaker and prints, per episode,
+      each speaker’s line count, character count and screen time in seconds.
+      Inputs may be in any supported subtitle format.
+
+  examining-one-anime-episodes-subtitles glossary <input> <json|csv> [--output <path>]
+      Lists every word glossed with furigana, with its reading, first
+      appearance and number of occurrences.
+
+  examining-one-anime-episodes-subtitles scripts <input> [--output <path>]
+      Breaks the dialogue down by script (kanji, hiragana, katakana and so on)
+      per unit and for the whole episode, as JSON.
+
+  examining-one-anime-episodes-subtitles kanji <input> [--output <path>]
+      Counts the kanji in the dialogue by list (kyōiku grade, jōyō, jinmeiyō
+      or hyōgai), with the hyōgai ratio and every hyōgai kanji, as JSON.
+
+  examining-one-anime-episodes-subtitles characters <input> <json|csv> [--output <path>]
+      Lists every character of the dialogue, most frequent first, with its
+      script, kanji list, number of units and first and last appearance.
+
+Options:
+  --output <path>       Write to <path> instead of standard output
+  --fail-on-negative    Fail instead of clamping timestamps that go below zero
+  --fps <rate>          Read MicroDVD inputs that don’t declare a framerate at
+                        <rate> instead of 23.976";
+
+#[derive(Debug)]
+pub struct UsageError(String);
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n\n{}", self.0, USAGE)
+    }
+}
+
+impl Error for UsageError {}
+
+fn usage_error(message: &str) -> Box<dyn Error> {
+    Box::new(UsageError(message.to_string()))
+}
+
+/// Options shared by every command.
+#[derive(Debug)]
+struct OutputOptions {
+    output: Option<String>,
+    policy: OutOfRangePolicy,
+    /// The framerate of MicroDVD inputs without a `{1}{1}` header.
+    fps: Framerate,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            output: None,
+            policy: OutOfRangePolicy::default(),
+            fps: Framerate::NTSC_FILM,
+        }
+    }
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    //! Runs the command named by `args[0]`; `args` excludes the program name.
+
+    let (positional, options) = split_options(args)?;
+
+    match positional.as_slice() {
+        ["shift", input, offset] => {
+            let offset_millis = parse_signed_millis(offset)?;
+            let (units, report) = read_units(input)?;
+            let shifted = shift(&units, offset_millis, options.policy)?;
+            write_units(&shifted, &report, &options)
+        }
+        ["resync", input, first, second] => {
+            let (first, second) = (parse_anchor(first)?, parse_anchor(second)?);
+            let (units, report) = read_units(input)?;
+            let resynced = resync(&units, first, second, options.policy)?;
+            write_units(&resynced, &report, &options)
+        }
+        ["framerate", input, source, target] => {
+            let (source, target) = (parse_framerate(source)?, parse_framerate(target)?);
+            let (units, report) = read_units(input)?;
+            let converted = convert_framerate(&units, source, target, options.policy)?;
+            write_units(&converted, &report, &options)
+        }
+        ["speakers", inputs @ ..] if !inputs.is_empty() => print_speaker_stats(inputs, options.fps),
+        ["glossary", input, format] => {
+            let (_, units) = read_subtitle_file(input, options.fps)?;
+            let glossary = harvest_furigana(&units);
+            let mut destination = open_output(&options)?;
+            match *format {
+                "json" => writeln!(destination, "{}", glossary.to_json()?)?,
+                "csv" => glossary.write_csv(&mut destination)?,
+                other => return Err(usage_error(&format!("Unknown glossary format {}", other))),
+            }
+            Ok(destination.flush()?)
+        }
+        ["scripts", input] => {
+            let (_, units) = read_subtitle_file(input, options.fps)?;
+            let mut destination = open_output(&options)?;
+            writeln!(
+                destination,
+                "{}",
+                EpisodeBreakdown::from_units(&units).to_json()?
+            )?;
+            Ok(destination.flush()?)
+        }
+        ["kanji", input] => {
+            let (_, units) = read_subtitle_file(input, options.fps)?;
+            let mut destination = open_output(&options)?;
+            writeln!(
+                destination,
+                "{}",
+                KanjiReport::from_units(&units).to_json()?
+            )?;
+            Ok(destination.flush()?)
+        }
+        ["characters", input, format] => {
+            let (_, units) = read_subtitle_file(input, options.fps)?;
+            let table = CharacterTable::builder().build(&units)?;
+            let mut destination = open_output(&options)?;
+            match *format {
+                "json" => writeln!(destination, "{}", table.to_json()?)?,
+                "csv" => table.write_csv(&mut destination)?,
+                other => return Err(usage_error(&format!("Unknown table format {}", other))),
+            }
+            Ok(destination.flush()?)
+        }
+        ["help"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(usage_error(
+            "Unrecognised command or wrong number of arguments",
+        )),
+    }
+}
+
+fn split_options(args: &[String]) -> Result<(Vec<&str>, OutputOptions), Box<dyn Error>> {
+    let mut positional: Vec<&str> = Vec::new();
+    let mut options = OutputOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| usage_error("--output needs a path"))?;
+                options.output = Some(path.clone());
+            }
+            "--fail-on-negative" => options.policy = OutOfRangePolicy::Fail,
+            "--fps" => {
+                let rate = args
+                    .next()
+                    .ok_or_else(|| usage_error("--fps needs a framerate"))?;
+                options.fps = parse_framerate(rate)?;
+            }
+            // Single-dash arguments are negative offsets, not options.
+            other if other.starts_with("--") => {
+                return Err(usage_error(&format!("Unknown option {}", other)));
+            }
+            other => positional.push(other),
+        }
+    }
+
+    Ok((positional, options))
+}
+
+/// Parses `-1500`, `+1500`, `1500`, `-00:00:01,500` and so on into signed
+/// milliseconds.
+fn parse_signed_millis(raw: &str) -> Result<i64, Box<dyn Error>> {
+    let (sign, magnitude) = match raw.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, raw.strip_prefix('+').unwrap_or(raw)),
+    };
+
+    let millis = if magnitude.contains(':') {
+        let timestamp = magnitude
+            .parse::<Timestamp>()
+            .map_err(|e| usage_error(&format!("Invalid offset {}: {:?}", raw, e)))?;
+        timestamp.to_millis() as i64
+    } else {
+        magnitude
+            .parse::<i64>()
+            .map_err(|_| usage_error(&format!("Invalid offset {}", raw)))?
+    };
+
+    Ok(sign * millis)
+}
+
+fn parse_anchor(raw: &str) -> Result<Anchor, Box<dyn Error>> {
+    let invalid = || usage_error(&format!("Invalid anchor {}; expected <sub>=<actual>", raw));
+
+    let (subtitle, actual) = raw.split_once('=').ok_or_else(invalid)?;
+    Ok(Anchor {
+        subtitle: subtitle.parse().map_err(|_| invalid())?,
+        actual: actual.parse().map_err(|_| invalid())?,
+    })
+}
+
+fn parse_framerate(raw: &str) -> Result<Framerate, Box<dyn Error>> {
+    raw.parse()
+        .map_err(|_| usage_error(&format!("Invalid framerate {}", raw)))
+}
+
+fn read_units(input: &str) -> Result<(Vec<SubtitleUnit>, NormalisationReport), Box<dyn Error>> {
+    let mut units = SubtitleParser::new().parse_file(input)?;
+    let parsed: Vec<SubtitleUnit> = units.by_ref().collect::<Result<_, _>>()?;
+
+    Ok((parsed, units.normalisations().clone()))
+}
+
+/// Writes with the same line ending and byte-order mark as the input.
+fn write_units(
+    units: &[SubtitleUnit],
+    report: &NormalisationReport,
+    options: &OutputOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut writer = SrtWriter::new(open_output(options)?)
+        .line_ending(report.dominant_line_ending())
+        .byte_order_mark(report.byte_order_mark);
+    writer.write_units(units)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// The `--output` file, or standard output if there isn’t one.
+fn open_output(options: &OutputOptions) -> io::Result<Box<dyn Write>> {
+    Ok(match &options.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    })
+}
+
+/// One tab-separated row per speaker per episode, so episodes can be compared
+/// side by side in a spreadsheet.
+fn print_speaker_stats(inputs: &[&str], fps: Framerate) -> Result<(), Box<dyn Error>> {
+    let mut out = io::stdout().lock();
+    writeln!(out, "episode\tspeaker\tlines\tcharacters\tscreen_time_s")?;
+
+    for input in inputs {
+        let (_, units) = read_subtitle_file(input, fps)?;
+        for stats in speaker_stats(&attribute_speakers(&units)) {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{:.3}",
+                input,
+                stats.speaker.as_deref().unwrap_or("-"),
+                stats.lines,
+                stats.characters,
+                stats.screen_time.as_secs_f64()
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,736 @@
+use crate::dataprep::ingestion::{PathError, SafeFilePath};
+use crate::dataprep::normalisation::{LineEnding, NormalisedLines};
+use crate::types::srt_index::SrtIndex;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::{Timestamp, TimestampError};
+use crate::types::timing::Timing;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Write};
+use std::path::Path;
+
+/// `\N` is a hard line break inside event text.
+const LINE_BREAK: &str = "\\N";
+
+const STYLE_FORMAT: &str = "Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, \
+    OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, \
+    Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding";
+const DEFAULT_STYLE: &str = "Default,Arial,20,&H00FFFFFF,&H000000FF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,2,2,2,10,10,10,1";
+const EVENT_FORMAT: &str =
+    "Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text";
+
+/// A parsed Advanced SubStation Alpha (`.ass`) or SubStation Alpha (`.ssa`)
+/// script. Sections other than `[Script Info]`, `[V4+ Styles]` (or
+/// `[V4 Styles]`) and `[Events]`, such as embedded fonts, are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssScript {
+    /// `Key: Value` lines of `[Script Info]`, in source order.
+    pub script_info: Vec<(String, String)>,
+    pub styles: Vec<AssStyle>,
+    pub events: Vec<AssEvent>,
+}
+
+/// A style as `(field, value)` pairs in the order of its section’s `Format`
+/// line, so SSA and ASS styles (which have different fields) both fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssStyle {
+    pub fields: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssEventKind {
+    Dialogue,
+    /// A commented-out line; it has timing and text but is never shown.
+    Comment,
+}
+
+/// One `Dialogue` or `Comment` line of `[Events]`.
+///
+/// The unit’s index is the event’s 1-based position in `[Events]`, and its
+/// lines are the text split on `\N`, with override tags such as `{\i1}` left
+/// in. [`strip_override_tags`] removes them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssEvent {
+    pub kind: AssEventKind,
+    /// Higher layers are drawn on top. SSA scripts have no layers, so their
+    /// events are all on layer 0.
+    pub layer: u32,
+    pub style: String,
+    /// The `Name` field, which names the speaking character.
+    pub actor: String,
+    pub margin_l: u32,
+    pub margin_r: u32,
+    pub margin_v: u32,
+    pub effect: String,
+    pub unit: SubtitleUnit,
+}
+
+#[derive(Debug)]
+pub enum AssError {
+    Io(io::Error),
+    Path(PathError),
+    /// A `Style`, `Dialogue` or `Comment` line came before its section’s
+    /// `Format` line.
+    MissingFormat {
+        line_number: usize,
+    },
+    /// A `Format` line lacks a field every event needs, e.g. `Start`.
+    MissingField {
+        line_number: usize,
+        field: &'static str,
+    },
+    /// A line has fewer fields than its `Format` line, a field that doesn’t
+    /// parse, or an `End` earlier than its `Start`.
+    MalformedLine {
+        line_number: usize,
+        message: String,
+    },
+    Timestamp {
+        line_number: usize,
+        error: TimestampError,
+    },
+}
+
+impl Display for AssError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssError::Io(e) => write!(f, "I/O error: {}", e),
+            AssError::Path(e) => write!(f, "Path error: {}", e),
+            AssError::MissingFormat { line_number } => {
+                write!(f, "Line {}: no Format line before this one", line_number)
+            }
+            AssError::MissingField { line_number, field } => {
+                write!(f, "Line {}: Format has no {} field", line_number, field)
+            }
+            AssError::MalformedLine {
+                line_number,
+                message,
+            } => write!(f, "Line {}: {}", line_number, message),
+            AssError::Timestamp { line_number, error } => {
+                write!(f, "Line {}: invalid timestamp: {:?}", line_number, error)
+            }
+        }
+    }
+}
+
+impl Error for AssError {}
+
+impl From<io::Error> for AssError {
+    fn from(error: io::Error) -> Self {
+        AssError::Io(error)
+    }
+}
+
+impl From<PathError> for AssError {
+    fn from(error: PathError) -> Self {
+        AssError::Path(error)
+    }
+}
+
+impl AssScript {
+    pub fn dialogue(&self) -> impl Iterator<Item = &AssEvent> {
+        self.events
+            .iter()
+            .filter(|event| event.kind == AssEventKind::Dialogue)
+    }
+
+    /// The `Dialogue` events as plain units, renumbered from 1. Comments are
+    /// left out.
+    pub fn units(&self) -> Vec<SubtitleUnit> {
+        self.dialogue()
+            .zip(1..)
+            .map(|(event, index)| SubtitleUnit {
+                index: SrtIndex::from(index),
+                ..event.unit.clone()
+            })
+            .collect()
+    }
+
+    pub fn style(&self, name: &str) -> Option<&AssStyle> {
+        self.styles.iter().find(|style| style.name() == Some(name))
+    }
+}
+
+impl AssStyle {
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(field))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.get("Name")
+    }
+
+    /// The style [`AssWriter`] uses for units written from SRT: white 20pt
+    /// Arial with a black outline, centred at the bottom.
+    pub fn default_style() -> Self {
+        AssStyle {
+            fields: split_format(STYLE_FORMAT)
+                .into_iter()
+                .zip(DEFAULT_STYLE.split(','))
+                .map(|(name, value)| (name, value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Which section the reader is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    ScriptInfo,
+    Styles,
+    Events,
+    Other,
+}
+
+/// Positions of the fields an event needs within its `Format` line.
+#[derive(Debug, Clone)]
+struct EventFormat {
+    len: usize,
+    layer: Option<usize>,
+    start: usize,
+    end: usize,
+    style: usize,
+    actor: Option<usize>,
+    margin_l: Option<usize>,
+    margin_r: Option<usize>,
+    margin_v: Option<usize>,
+    effect: Option<usize>,
+    text: usize,
+}
+
+impl EventFormat {
+    fn parse(raw: &str, line_number: usize) -> Result<Self, AssError> {
+        let fields = split_format(raw);
+        let find = |name: &str| {
+            fields
+                .iter()
+                .position(|field| field.eq_ignore_ascii_case(name))
+        };
+        let require =
+            |field: &'static str| find(field).ok_or(AssError::MissingField { line_number, field });
+
+        let text = require("Text")?;
+        if text != fields.len() - 1 {
+            return Err(AssError::MalformedLine {
+                line_number,
+                message: "Text must be the last field of Format".to_string(),
+            });
+        }
+
+        Ok(EventFormat {
+            len: fields.len(),
+            layer: find("Layer"),
+            start: require("Start")?,
+            end: require("End")?,
+            style: require("Style")?,
+            actor: find("Name").or_else(|| find("Actor")),
+            margin_l: find("MarginL"),
+            margin_r: find("MarginR"),
+            margin_v: find("MarginV"),
+            effect: find("Effect"),
+            text,
+        })
+    }
+}
+
+pub fn read_ass<R: BufRead>(reader: R) -> Result<AssScript, AssError> {
+    //! Reads a whole `.ass` or `.ssa` script. Field positions come from each
+    //! section’s `Format` line, so reordered or missing optional fields are
+    //! fine. The text is always the last field and may contain commas.
+
+    let mut lines = NormalisedLines::new(reader);
+    let mut script = AssScript::default();
+    let mut section = Section::Other;
+    let mut style_format: Option<Vec<String>> = None;
+    let mut event_format: Option<EventFormat> = None;
+
+    while let Some(line) = lines.read_line()? {
+        let line_number = line.line_number;
+        let text = line.text.trim_start();
+
+        if text.is_empty() || text.starts_with(';') {
+            continue;
+        } else if text.starts_with('[') && text.ends_with(']') {
+            section = match &text[1..text.len() - 1] {
+                "Script Info" => Section::ScriptInfo,
+                "V4+ Styles" | "V4 Styles" | "V4 Styles+" => Section::Styles,
+                "Events" => Section::Events,
+                _ => Section::Other,
+            };
+            continue;
+        }
+
+        let Some((key, value)) = text.split_once(':') else {
+            continue;
+        };
+        let value = value.trim_start();
+
+        match (section, key) {
+            (Section::ScriptInfo, _) => {
+                script
+                    .script_info
+                    .push((key.to_string(), value.to_string()));
+            }
+            (Section::Styles, "Format") => style_format = Some(split_format(value)),
+            (Section::Styles, "Style") => {
+                let format = style_format
+                    .as_ref()
+                    .ok_or(AssError::MissingFormat { line_number })?;
+                let values = split_fields(value, format.len(), line_number)?;
+                script.styles.push(AssStyle {
+                    fields: format
+                        .iter()
+                        .cloned()
+                        .zip(values.into_iter().map(|v| v.trim().to_string()))
+                        .collect(),
+                });
+            }
+            (Section::Events, "Format") => {
+                event_format = Some(EventFormat::parse(value, line_number)?)
+            }
+            (Section::Events, "Dialogue" | "Comment") => {
+                let format = event_format
+                    .as_ref()
+                    .ok_or(AssError::MissingFormat { line_number })?;
+                let kind = if key == "Dialogue" {
+                    AssEventKind::Dialogue
+                } else {
+                    AssEventKind::Comment
+                };
+                let position = script.events.len() as u32 + 1;
+                script
+                    .events
+                    .push(parse_event(value, kind, format, position, line_number)?);
+            }
+            // `Picture`, `Sound`, `Movie` and `Command` events aren’t subtitles.
+            _ => {}
+        }
+    }
+
+    Ok(script)
+}
+
+pub fn read_ass_str(content: &str) -> Result<AssScript, AssError> {
+    read_ass(Cursor::new(content))
+}
+
+pub fn read_ass_file(path: impl AsRef<Path>) -> Result<AssScript, AssError> {
+    // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::with_extensions(raw_path, &["ass", "ssa"])?;
+
+    read_ass(BufReader::new(File::open(checked_path)?))
+}
+
+pub fn strip_override_tags(text: &str) -> String {
+    //! Removes `{...}` override blocks (`{\i1}`, `{\pos(320,50)}`, ...) and
+    //! turns the `\h` hard space into a no-break space.
+
+    let mut plain = String::with_capacity(text.len());
+    let mut depth = 0;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            _ if depth == 0 => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.replace("\\h", "\u{00A0}")
+}
+
+fn split_format(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// Splits into exactly `count` fields; commas in the last field are kept.
+fn split_fields(raw: &str, count: usize, line_number: usize) -> Result<Vec<&str>, AssError> {
+    let fields: Vec<&str> = raw.splitn(count, ',').collect();
+    if fields.len() < count {
+        return Err(AssError::MalformedLine {
+            line_number,
+            message: format!("Expected {} fields, found {}", count, fields.len()),
+        });
+    }
+    Ok(fields)
+}
+
+fn parse_event(
+    raw: &str,
+    kind: AssEventKind,
+    format: &EventFormat,
+    position: u32,
+    line_number: usize,
+) -> Result<AssEvent, AssError> {
+    let fields = split_fields(raw, format.len, line_number)?;
+    let field = |index: Option<usize>| index.map_or("", |i| fields[i].trim());
+    let number = |index: Option<usize>| -> Result<u32, AssError> {
+        let value = field(index);
+        if value.is_empty() {
+            return Ok(0);
+        }
+        value.parse().map_err(|_| AssError::MalformedLine {
+            line_number,
+            message: format!("{} isn’t a whole number", value),
+        })
+    };
+    let timestamp = |index: usize| {
+        parse_timestamp(fields[index].trim())
+            .map_err(|error| AssError::Timestamp { line_number, error })
+    };
+
+    let (start, end) = (timestamp(format.start)?, timestamp(format.end)?);
+    if start > end {
+        return Err(AssError::MalformedLine {
+            line_number,
+            message: "Start timestamp is later than end timestamp".to_string(),
+        });
+    }
+
+    Ok(AssEvent {
+        kind,
+        layer: number(format.layer)?,
+        style: field(Some(format.style)).to_string(),
+        actor: field(format.actor).to_string(),
+        margin_l: number(format.margin_l)?,
+        margin_r: number(format.margin_r)?,
+        margin_v: number(format.margin_v)?,
+        effect: field(format.effect).to_string(),
+        unit: SubtitleUnit {
+            index: SrtIndex::from(position),
+            timing: Timing { start, end },
+            lines: fields[format.text]
+                .split(LINE_BREAK)
+                .map(str::to_string)
+                .collect(),
+        },
+    })
+}
+
+/// Parses `h:mm:ss.cc`. The fraction is usually centiseconds, but one to three
+/// digits are accepted as a fraction of a second. SubViewer uses the same form.
+pub(crate) fn parse_timestamp(s: &str) -> Result<Timestamp, TimestampError> {
+    if s.is_empty() {
+        return Err(TimestampError::EmptyString);
+    }
+
+    let malformed = |msg: &str| TimestampError::malformed(msg, s);
+    let (clock, fraction) = s
+        .split_once('.')
+        .ok_or_else(|| malformed("Timestamps need a . before the centiseconds"))?;
+    let fields: Vec<&str> = clock.split(':').collect();
+    let [hours, minutes, seconds] = fields.as_slice() else {
+        return Err(malformed("Timestamp must be h:mm:ss.cc"));
+    };
+
+    let all_digits = |field: &str| !field.is_empty() && field.chars().all(|c| c.is_ascii_digit());
+    if fraction.len() > 3
+        || ![*hours, *minutes, *seconds, fraction]
+            .into_iter()
+            .all(all_digits)
+    {
+        return Err(malformed(
+            "Timestamp fields must be digits, with up to three after the .",
+        ));
+    }
+
+    // The SRT parser does the range checks (minutes and seconds up to 59,
+    // hours up to 255).
+    format!("{}:{}:{},{:0<3}", hours, minutes, seconds, fraction).parse()
+}
+
+/// `h:mm:ss.cc`, rounded to the nearest centisecond. SubViewer uses the same
+/// form with two-digit hours, so `hour_digits` pads the hours with zeros.
+///
+/// Rounding never goes past [`Timestamp::MAX`], so the result always reads
+/// back with [`parse_timestamp`].
+pub(crate) fn format_timestamp(timestamp: Timestamp, hour_digits: usize) -> String {
+    let centis = ((timestamp.to_millis() + 5) / 10).min(Timestamp::MAX.to_millis() / 10);
+    format!(
+        "{:0hour_digits$}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        centis / 6_000 % 60,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+/// Writes ASS scripts to any `io::Write`. Scripts are always written as ASS
+/// (`ScriptType: v4.00+`), even if they were read from SSA.
+///
+/// ASS times have centisecond precision, so timestamps are rounded to the
+/// nearest 10 ms.
+///
+/// ```text
+/// let mut writer = AssWriter::new(File::create("out.ass")?);
+/// writer.write_units(&units)?;
+/// ```
+#[derive(Debug)]
+pub struct AssWriter<W: Write> {
+    inner: W,
+    line_ending: LineEnding,
+    header_written: bool,
+}
+
+impl<W: Write> AssWriter<W> {
+    /// Creates a writer using `\n` line endings.
+    pub fn new(inner: W) -> Self {
+        AssWriter {
+            inner,
+            line_ending: LineEnding::Lf,
+            header_written: false,
+        }
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Writes `[Script Info]`, the styles and every event of `script`.
+    pub fn write_script(&mut self, script: &AssScript) -> io::Result<()> {
+        self.write_header(&script.script_info, &script.styles)?;
+        for event in &script.events {
+            self.write_event(event)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_event(&mut self, event: &AssEvent) -> io::Result<()> {
+        self.write_header(&[], &[])?;
+
+        let kind = match event.kind {
+            AssEventKind::Dialogue => "Dialogue",
+            AssEventKind::Comment => "Comment",
+        };
+        write!(
+            self.inner,
+            "{}: {},{},{},{},{},{},{},{},{},{}{}",
+            kind,
+            event.layer,
+            format_timestamp(event.unit.timing.start, 1),
+            format_timestamp(event.unit.timing.end, 1),
+            event.style,
+            event.actor,
+            event.margin_l,
+            event.margin_r,
+            event.margin_v,
+            event.effect,
+            event.unit.lines.join(LINE_BREAK),
+            self.line_ending.as_str()
+        )
+    }
+
+    /// Writes `unit` as a `Dialogue` event in the `Default` style. Writing
+    /// units from SRT this way gives a complete script.
+    pub fn write_unit(&mut self, unit: &SubtitleUnit) -> io::Result<()> {
+        self.write_event(&AssEvent {
+            kind: AssEventKind::Dialogue,
+            layer: 0,
+            style: "Default".to_string(),
+            actor: String::new(),
+            margin_l: 0,
+            margin_r: 0,
+            margin_v: 0,
+            effect: String::new(),
+            unit: unit.clone(),
+        })
+    }
+
+    pub fn write_units<'a>(
+        &mut self,
+        units: impl IntoIterator<Item = &'a SubtitleUnit>,
+    ) -> io::Result<()> {
+        for unit in units {
+            self.write_unit(unit)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes everything up to and including the `[Events]` `Format` line,
+    /// unless it has been written already. With no styles, the
+    /// [default style](AssStyle::default_style) is written.
+    fn write_header(
+        &mut self,
+        script_info: &[(String, String)],
+        styles: &[AssStyle],
+    ) -> io::Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+        let ending = self.line_ending.as_str();
+
+        write!(self.inner, "[Script Info]{}", ending)?;
+        if !script_info.iter().any(|(key, _)| key == "ScriptType") {
+            write!(self.inner, "ScriptType: v4.00+{}", ending)?;
+        }
+        if script_info.is_empty() {
+            write!(self.inner, "PlayResX: 384{0}PlayResY: 288{0}", ending)?;
+        }
+        for (key, value) in script_info {
+            let value = if key == "ScriptType" { "v4.00+" } else { value };
+            write!(self.inner, "{}: {}{}", key, value, ending)?;
+        }
+
+        let default_style = [AssStyle::default_style()];
+        let styles = if styles.is_empty() {
+            &default_style[..]
+        } else {
+            styles
+        };
+        write!(self.inner, "{0}[V4+ Styles]{0}", ending)?;
+        let format: Vec<&str> = styles[0].fields.iter().map(|(n, _)| n.as_str()).collect();
+        write!(self.inner, "Format: {}{}", format.join(", "), ending)?;
+        for style in styles {
+            let values: Vec<&str> = style.fields.iter().map(|(_, v)| v.as_str()).collect();
+            write!(self.inner, "Style: {}{}", values.join(","), ending)?;
+        }
+
+        write!(self.inner, "{0}[Events]{0}", ending)?;
+        write!(self.inner, "Format: {}{}", EVENT_FORMAT, ending)
+    }
+}
+
+pub fn to_ass_string(units: &[SubtitleUnit]) -> String {
+    //! Writes `units` as an ASS script with `\n` line endings, using the
+    //! [default style](AssStyle::default_style).
+
+    let mut writer = AssWriter::new(Vec::new());
+    writer
+        .write_units(units)
+        .expect("Writing to a Vec can’t fail");
+
+    String::from_utf8(writer.into_inner()).expect("ASS output is always UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    const INPUT: &str = "\u{FEFF}[Script Info]\r
+; Script generated by Aegisub\r
+Title: サイコパス 第1話\r
+ScriptType: v4.00+\r
+PlayResX: 1920\r
+\r
+[V4+ Styles]\r
+Format: Name, Fontname, Fontsize, PrimaryColour, Alignment\r
+Style: Default,Hiragino Sans,72,&H00FFFFFF,2\r
+Style: Sign,Hiragino Sans,48,&H0000FFFF,8\r
+\r
+[Events]\r
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\r
+Comment: 0,0:00:00.00,0:00:05.00,Default,,0,0,0,,Timing check\r
+Dialogue: 0,0:00:12.85,0:00:24.56,Default,槙島,0,0,0,,{\\i1}君は、{\\i0}\\N何を望む？\r
+Dialogue: 1,1:02:03.4,1:02:05.00,Sign,,0,0,0,,{\\pos(960,80)}公安局\r
+";
+
+    #[test]
+    fn test_read_ass() {
+        let script = read_ass_str(INPUT).unwrap();
+        assert_eq!(script.script_info[0].1, "サイコパス 第1話");
+        assert_eq!(script.styles.len(), 2);
+        assert_eq!(script.style("Sign").unwrap().get("Alignment"), Some("8"));
+
+        assert_eq!(script.events.len(), 3);
+        assert_eq!(script.events[0].kind, AssEventKind::Comment);
+        let line = &script.events[1];
+        assert_eq!((line.layer, line.style.as_str()), (0, "Default"));
+        assert_eq!(line.actor, "槙島");
+        assert_eq!(line.unit.lines, vec!["{\\i1}君は、{\\i0}", "何を望む？"]);
+        assert_eq!(strip_override_tags(&line.unit.lines[0]), "君は、");
+
+        let units = script.units();
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].index.value(), 1);
+        assert_eq!(units[1].timing.to_string(), "01:02:03,400 --> 01:02:05,000");
+        assert_eq!(script.events[2].layer, 1);
+    }
+
+    #[test]
+    fn test_write_script_round_trips() {
+        let script = read_ass_str(INPUT).unwrap();
+        let mut writer = AssWriter::new(Vec::new());
+        writer.write_script(&script).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert!(written.contains("Dialogue: 1,1:02:03.40,1:02:05.00,Sign,,0,0,0,,"));
+        assert_eq!(read_ass_str(&written).unwrap(), script);
+    }
+
+    #[test]
+    fn test_srt_episode_converts_to_ass() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let script = read_ass_str(&to_ass_string(&units)).unwrap();
+
+        assert_eq!(script.styles, vec![AssStyle::default_style()]);
+        assert_eq!(script.events.len(), units.len());
+        for (event, unit) in script.events.iter().zip(&units) {
+            assert_eq!(event.unit.lines, unit.lines);
+            // Rounded to the nearest centisecond.
+            let start = unit.timing.start.to_millis();
+            assert!(event.unit.timing.start.to_millis().abs_diff(start) <= 5);
+        }
+    }
+
+    #[test]
+    fn test_timestamps_near_the_limits() {
+        let latest = format_timestamp(Timestamp::MAX, 1);
+        assert_eq!(latest, "255:59:59.99");
+        assert_eq!(
+            parse_timestamp(&latest).unwrap().to_string(),
+            "255:59:59,990"
+        );
+
+        // An hour field too long for any integer is an error, not a panic.
+        assert!(parse_timestamp("99999999999999999999999:00:01.00").is_err());
+    }
+
+    #[test]
+    fn test_read_ssa_without_layers() {
+        let input = "[Script Info]\nScriptType: v4.00\n\n[Events]\n\
+            Format: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n\
+            Dialogue: Marked=0,0:00:01.00,0:00:02.00,*Default,NTP,0000,0000,0000,,Hello, world\n";
+        let script = read_ass_str(input).unwrap();
+
+        let event = &script.events[0];
+        assert_eq!(event.layer, 0);
+        assert_eq!(event.actor, "NTP");
+        assert_eq!(event.unit.lines, vec!["Hello, world"]);
+
+        assert!(matches!(
+            read_ass_str("[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hi\n"),
+            Err(AssError::MissingFormat { line_number: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_event_ending_before_it_starts_is_an_error() {
+        let input = "[Events]
+            Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+            Dialogue: 0,0:00:05.00,0:00:01.00,Default,,0,0,0,,Hi
+";
+        assert!(matches!(
+            read_ass_str(input),
+            Err(AssError::MalformedLine { line_number: 3, .. })
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::types::segment::{Segment, spoken_text};
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timing::Timing;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// One stretch of dialogue from one unit, credited to one speaker. A unit with
+/// two speaker labels gives two of these, sharing the unit’s timing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributedLine {
+    pub unit_index: u32,
+    pub timing: Timing,
+    /// The label’s text without furigana, so `（狡噛(こうがみ)）` and `（狡噛）`
+    /// both give `狡噛`. `None` before the first label of the episode.
+    pub speaker: Option<String>,
+    /// `true` if the speaker was inherited from an earlier unit rather than
+    /// labelled in this one.
+    pub carried_over: bool,
+    /// The dialogue, with lines joined by `\n`.
+    pub text: String,
+    /// How many lines share the unit’s screen time.
+    speakers_in_unit: u32,
+}
+
+impl AttributedLine {
+    /// Characters of dialogue, not counting whitespace.
+    pub fn character_count(&self) -> usize {
+        self.text.chars().filter(|c| !c.is_whitespace()).count()
+    }
+
+    /// This line’s share of its unit’s screen time: the whole duration, or an
+    /// even split when the unit has several speakers.
+    pub fn screen_time(&self) -> Duration {
+        self.timing.duration() / self.speakers_in_unit
+    }
+}
+
+/// Totals for one speaker across everything passed to [`speaker_stats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpeakerStats {
+    /// `None` collects the dialogue before the first label.
+    pub speaker: Option<String>,
+    pub lines: usize,
+    pub characters: usize,
+    pub screen_time: Duration,
+}
+
+pub fn attribute_speakers(units: &[SubtitleUnit]) -> Vec<AttributedLine> {
+    //! Credits the dialogue in `units` to speakers.
+    //!
+    //! A speaker label holds until the next one, so unlabelled units go to
+    //! whoever was last named, even across sound effects and music. A label
+    //! partway through a unit splits it: the text before goes to the current
+    //! speaker and the text after to the new one.
+    //!
+    //! Units without dialogue, such as `（足音）` or `♪～`, give no lines.
+
+    let mut attributed = Vec::new();
+    let mut current: Option<String> = None;
+
+    for unit in units {
+        let mut in_unit: Vec<AttributedLine> = Vec::new();
+        let mut carried_over = true;
+        let mut stretch: Vec<Segment> = Vec::new();
+
+        for segment in unit.segments() {
+            if let Segment::Speaker(label) = segment {
+                push_line(&mut in_unit, unit, &current, carried_over, &stretch);
+                stretch.clear();
+                current = Some(spoken_text(&label));
+                carried_over = false;
+            } else {
+                stretch.push(segment);
+            }
+        }
+        push_line(&mut in_unit, unit, &current, carried_over, &stretch);
+
+        let speakers_in_unit = in_unit.len() as u32;
+        for line in &mut in_unit {
+            line.speakers_in_unit = speakers_in_unit;
+        }
+        attributed.append(&mut in_unit);
+    }
+
+    attributed
+}
+
+pub fn speaker_stats(lines: &[AttributedLine]) -> Vec<SpeakerStats> {
+    //! Line counts, character counts and screen time per speaker, most
+    //! talkative (by characters) first. Pass the lines of several episodes
+    //! together to total across them.
+
+    let mut stats: Vec<SpeakerStats> = Vec::new();
+    let mut positions: HashMap<Option<String>, usize> = HashMap::new();
+
+    for line in lines {
+        let position = *positions.entry(line.speaker.clone()).or_insert_with(|| {
+            stats.push(SpeakerStats {
+                speaker: line.speaker.clone(),
+                lines: 0,
+                characters: 0,
+                screen_time: Duration::ZERO,
+            });
+            stats.len() - 1
+        });
+
+        let entry = &mut stats[position];
+        entry.lines += 1;
+        entry.characters += line.character_count();
+        entry.screen_time += line.screen_time();
+    }
+
+    stats.sort_by_key(|entry| Reverse(entry.characters));
+    stats
+}
+
+/// Adds the dialogue in `segments` as a line of `unit`, unless there isn’t any.
+fn push_line(
+    lines: &mut Vec<AttributedLine>,
+    unit: &SubtitleUnit,
+    speaker: &Option<String>,
+    carried_over: bool,
+    segments: &[Segment],
+) {
+    let text = spoken_text(segments);
+    let text = text.trim();
+    if !text.is_empty() {
+        lines.push(AttributedLine {
+            unit_index: unit.index.value(),
+            timing: unit.timing,
+            speaker: speaker.clone(),
+            carried_over,
+            text: text.to_string(),
+            speakers_in_unit: 1,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_carry_over_and_multi_speaker_units() {
+        let input = "1\n00:00:01,000 --> 00:00:03,000\n（朱(あかね)）\nはい\n\n\
+            2\n00:00:04,000 --> 00:00:05,000\n（足音）\n\n\
+            3\n00:00:06,000 --> 00:00:08,000\nすいません\n（狡噛）ああ\n";
+        let units = SubtitleParser::new().read_str(input).unwrap();
+        let lines = attribute_speakers(&units);
+
+        let summary: Vec<(u32, Option<&str>, bool, &str)> = lines
+            .iter()
+            .map(|line| {
+                (
+                    line.unit_index,
+                    line.speaker.as_deref(),
+                    line.carried_over,
+                    line.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, Some("朱"), false, "はい"),
+                (3, Some("朱"), true, "すいません"),
+                (3, Some("狡噛"), false, "ああ"),
+            ]
+        );
+
+        let stats = speaker_stats(&lines);
+        assert_eq!(stats[0].speaker.as_deref(), Some("朱"));
+        assert_eq!((stats[0].lines, stats[0].characters), (2, 7));
+        assert_eq!(stats[0].screen_time, Duration::from_millis(3000));
+        assert_eq!(stats[1].screen_time, Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_episode_speakers() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let stats = speaker_stats(&attribute_speakers(&units));
+        let find = |name: &str| {
+            stats
+                .iter()
+                .find(|entry| entry.speaker.as_deref() == Some(name))
+                .unwrap()
+        };
+
+        // Furigana on a label doesn’t make a second speaker.
+        assert!(
+            stats
+                .iter()
+                .filter_map(|entry| entry.speaker.as_deref())
+                .all(|name| !name.contains('('))
+        );
+        assert!(find("朱").lines > 0);
+        assert!(find("狡噛").screen_time > Duration::ZERO);
+    }
+}
@@ -1,51 +1,146 @@
 //! 2026-03-10 This is no longer required as part of the project’s scope, since
 //! it is now more generic and doesn’t solely focus on Japanese.
 
-use crate::dataprep::ingestion::ingest_json_file;
+use crate::dataprep::pipeline::CleaningPipeline;
+use crate::types::subtitle_unit::SubtitleUnit;
 use serde::Deserialize;
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::LazyLock;
 
-const MINI_KANA_JSON_PATH: &str = "data/raw/mini_kana_mappings.json";
-const UNWANTED_CHARACTERS_PATH: &str = "data/raw/unwanted_characters.txt";
+/// `data/raw/mini_kana_mappings.json`, compiled in so that cleaning works from
+/// any directory.
+const MINI_KANA_JSON: &str = include_str!("../../data/raw/mini_kana_mappings.json");
+/// `data/raw/unwanted_characters.txt`, compiled in for the same reason.
+const UNWANTED_CHARACTERS: &str = include_str!("../../data/raw/unwanted_characters.txt");
 
-/// Newtype representing the **keys** in the file accessed by [`MINI_KANA_JSON_PATH`].
-#[derive(Deserialize, Eq, PartialEq, Hash, Debug)]
-struct SmallKana(char);
+static MINI_KANA_MAPPINGS: LazyLock<HashMap<SmallKana, RegularKana>> = LazyLock::new(|| {
+    serde_json::from_str(MINI_KANA_JSON).expect("The bundled mini-kana mappings are valid JSON")
+});
 
-/// Newtype representing the _values_ in the file accessed by [`MINI_KANA_JSON_PATH`].
-#[derive(Deserialize, Eq, PartialEq, Hash, Debug)]
-struct RegularKana(char);
+static UNWANTED_CHARACTER_SET: LazyLock<HashSet<char>> =
+    LazyLock::new(|| UNWANTED_CHARACTERS.chars().collect());
 
-pub fn clean_subtitles(raw_input: &str) -> Result<String, Box<dyn std::error::Error>> {
+/// What [`remove_parentheses_and_contents`] does with text whose parentheses
+/// don’t balance. Every policy still reports the unmatched parentheses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParenthesisPolicy {
+    /// Return the first unmatched parenthesis as an error.
+    #[default]
+    Fail,
+    /// Close any open parentheses at the end of the text, removing everything
+    /// after them, and drop stray closing parentheses.
+    AutoClose,
+    /// Return the text unchanged, parentheses and all.
+    LeaveUntouched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnmatchedKind {
+    /// An opening parenthesis that is never closed.
+    Opening,
+    /// A closing parenthesis with no opening one before it.
+    Closing,
+}
+
+/// A parenthesis without a partner. `offset` counts characters (not bytes)
+/// from the start of the text, or of the unit’s lines joined by `\n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnbalancedParenthesis {
+    pub kind: UnmatchedKind,
+    pub bracket: char,
+    pub unit_index: Option<u32>,
+    pub offset: usize,
+}
+
+impl Display for UnbalancedParenthesis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            UnmatchedKind::Opening => "Unclosed",
+            UnmatchedKind::Closing => "Unopened",
+        };
+        write!(
+            f,
+            "{} parenthesis {} at character {}",
+            kind, self.bracket, self.offset
+        )?;
+        if let Some(index) = self.unit_index {
+            write!(f, " of unit {}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for UnbalancedParenthesis {}
+
+/// Text with its parentheses removed, and any that didn’t balance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrippedText {
+    pub text: String,
+    pub unbalanced: Vec<UnbalancedParenthesis>,
+}
+
+/// Newtype representing the **keys** in [`MINI_KANA_JSON`].
+#[derive(Deserialize, Eq, PartialEq, Hash, Debug, Clone)]
+pub(crate) struct SmallKana(pub(crate) char);
+
+/// Newtype representing the _values_ in [`MINI_KANA_JSON`].
+#[derive(Deserialize, Eq, PartialEq, Hash, Debug, Clone)]
+pub(crate) struct RegularKana(pub(crate) char);
+
+pub fn clean_subtitles(
+    raw_input: &str,
+    policy: ParenthesisPolicy,
+) -> Result<String, Box<dyn std::error::Error>> {
     //! Cleans an ingested subtitle string in the following order:
     //!
     //! - Remove parentheses and their contents
+    //!   ([`remove_parentheses_and_contents`], which handles unbalanced
+    //!   parentheses according to `policy`)
     //! - Remove unwanted characters ([`remove_unwanted_characters`]; see also
     //!   [`helper_dedupe_and_sort`] for a definition of unwanted characters)
     //! - Convert mini-kana characters to their regular-sized counterparts
     //!   ([`convert_mini_kana_to_regular`])
     //!
-    //! After this step, the output is ready for **subtitle processing**.
+    //! After this step, the output is ready for **subtitle processing**. For
+    //! any other order or set of steps, build a [`CleaningPipeline`] instead.
+
+    Ok(CleaningPipeline::standard(policy).apply(raw_input)?)
+}
+
+pub(crate) fn unwanted_characters() -> &'static HashSet<char> {
+    //! The bundled list of unwanted characters (see [`helper_dedupe_and_sort`]).
+
+    &UNWANTED_CHARACTER_SET
+}
+
+pub(crate) fn mini_kana_mappings() -> &'static HashMap<SmallKana, RegularKana> {
+    //! The bundled small-to-regular kana pairs.
 
-    let unwanted_characters_raw = fs::read_to_string(UNWANTED_CHARACTERS_PATH)?;
-    let unwanted_characters: HashSet<char> = unwanted_characters_raw.chars().collect();
+    &MINI_KANA_MAPPINGS
+}
 
-    let mini_kana_mappings: HashMap<SmallKana, RegularKana> =
-        ingest_json_file(MINI_KANA_JSON_PATH)?;
+pub(crate) fn read_unwanted_characters(path: impl AsRef<Path>) -> io::Result<HashSet<char>> {
+    //! Reads a replacement for the bundled unwanted characters: a text file
+    //! whose every character is unwanted.
 
-    let parentheses_and_their_contents_removed: String = remove_parentheses_and_contents(raw_input);
+    Ok(fs::read_to_string(path)?.chars().collect())
+}
 
-    let unwanted_chars_removed_and_small_kana_as_regular: String =
-        parentheses_and_their_contents_removed
-            .chars()
-            .filter(|x: &char| !unwanted_characters.contains(x))
-            .map(|x: char| convert_mini_kana_to_regular(&x, &mini_kana_mappings))
-            .collect();
+pub(crate) fn read_mini_kana_mappings(
+    path: impl AsRef<Path>,
+) -> io::Result<HashMap<SmallKana, RegularKana>> {
+    //! Reads a replacement for the bundled small-to-regular kana pairs, in the
+    //! same JSON form as `data/raw/mini_kana_mappings.json`.
 
-    Ok(unwanted_chars_removed_and_small_kana_as_regular)
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
 }
 
 pub fn helper_dedupe_and_sort(xs: &str) {
@@ -86,19 +181,36 @@ pub fn helper_dedupe_and_sort(xs: &str) {
     println!("{deduped_and_sorted}");
 }
 
-fn remove_parentheses_and_contents(input: &str) -> String {
+pub fn remove_parentheses_and_contents(
+    input: &str,
+    policy: ParenthesisPolicy,
+) -> Result<StrippedText, UnbalancedParenthesis> {
     //! Removes parentheses in a string, along with all characters enclosed
-    //! within. Works on both regular and full-width parentheses:
+    //! within. Works on both regular and full-width parentheses, which may be
+    //! nested inside each other:
     //!
     //! - Regular parentheses are `(` and `)`
     //! - Full-width parentheses are `（` and `）` (used in Japanese)
     //!
-    //! **Very important.** This function does _not_ check if there are equal
-    //! numbers of opening and closing parentheses. Should there be an unequal
-    //! number of opening and closing parenthesis symbols, the function will
-    //! still return an output, albeit one that is incorrect.
-    //!
-    //! **_Always check the input string._**
+    //! Unmatched parentheses are reported in the returned
+    //! [`StrippedText::unbalanced`], and what happens to the text depends on
+    //! `policy`; see [`ParenthesisPolicy`]. The reported `unit_index` is always
+    //! `None`; [`remove_unit_parentheses`] fills it in.
+
+    let unbalanced = find_unbalanced_parentheses(input);
+
+    if let Some(first) = unbalanced.first() {
+        match policy {
+            ParenthesisPolicy::Fail => return Err(first.clone()),
+            ParenthesisPolicy::LeaveUntouched => {
+                return Ok(StrippedText {
+                    text: input.to_string(),
+                    unbalanced,
+                });
+            }
+            ParenthesisPolicy::AutoClose => {}
+        }
+    }
 
     let mut result = String::new();
     let mut depth: u32 = 0;
@@ -106,16 +218,70 @@ fn remove_parentheses_and_contents(input: &str) -> String {
     for char in input.chars() {
         match char {
             '(' | '（' => depth += 1,
+            // A stray closing parenthesis is dropped.
             ')' | '）' => depth = depth.saturating_sub(1),
             _ if depth == 0 => result.push(char),
             _ => {} // Reminder: returns unit type (i.e. does nothing)
         }
     }
 
-    result
+    Ok(StrippedText {
+        text: result,
+        unbalanced,
+    })
 }
 
-fn convert_mini_kana_to_regular(
+pub fn remove_unit_parentheses(
+    unit: &SubtitleUnit,
+    policy: ParenthesisPolicy,
+) -> Result<StrippedText, UnbalancedParenthesis> {
+    //! Like [`remove_parentheses_and_contents`], for the lines of `unit` joined
+    //! by `\n`. An unclosed parenthesis is closed at the end of the unit rather
+    //! than running on into the next one.
+
+    let index = unit.index.value();
+    let with_index = |mut unbalanced: UnbalancedParenthesis| {
+        unbalanced.unit_index = Some(index);
+        unbalanced
+    };
+
+    let mut stripped =
+        remove_parentheses_and_contents(&unit.lines.join("\n"), policy).map_err(with_index)?;
+    stripped.unbalanced = stripped.unbalanced.into_iter().map(with_index).collect();
+    Ok(stripped)
+}
+
+fn find_unbalanced_parentheses(input: &str) -> Vec<UnbalancedParenthesis> {
+    let unmatched = |kind, bracket, offset| UnbalancedParenthesis {
+        kind,
+        bracket,
+        unit_index: None,
+        offset,
+    };
+
+    let mut unbalanced = Vec::new();
+    let mut open: Vec<(usize, char)> = Vec::new();
+
+    for (offset, char) in input.chars().enumerate() {
+        match char {
+            '(' | '（' => open.push((offset, char)),
+            // Popping in the guard matches the parenthesis as a side effect.
+            ')' | '）' if open.pop().is_none() => {
+                unbalanced.push(unmatched(UnmatchedKind::Closing, char, offset));
+            }
+            _ => {}
+        }
+    }
+    unbalanced.extend(
+        open.into_iter()
+            .map(|(offset, char)| unmatched(UnmatchedKind::Opening, char, offset)),
+    );
+    unbalanced.sort_by_key(|parenthesis| parenthesis.offset);
+
+    unbalanced
+}
+
+pub(crate) fn convert_mini_kana_to_regular(
     input: &char,
     kana_mapping: &HashMap<SmallKana, RegularKana>,
 ) -> char {
@@ -136,3 +302,52 @@ fn convert_mini_kana_to_regular(
 
     unwrapped_output
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_unbalanced_parentheses_follow_the_policy() {
+        let input = "（足音）えっ(ささやき";
+
+        let error = remove_parentheses_and_contents(input, ParenthesisPolicy::Fail).unwrap_err();
+        assert_eq!(error.kind, UnmatchedKind::Opening);
+        assert_eq!((error.bracket, error.offset), ('(', 6));
+
+        let closed = remove_parentheses_and_contents(input, ParenthesisPolicy::AutoClose).unwrap();
+        assert_eq!(closed.text, "えっ");
+        assert_eq!(closed.unbalanced.len(), 1);
+
+        let untouched =
+            remove_parentheses_and_contents(input, ParenthesisPolicy::LeaveUntouched).unwrap();
+        assert_eq!(untouched.text, input);
+
+        let stray = remove_parentheses_and_contents("はい）（足音）", ParenthesisPolicy::AutoClose)
+            .unwrap();
+        assert_eq!(stray.text, "はい");
+        assert_eq!(stray.unbalanced[0].kind, UnmatchedKind::Closing);
+        assert_eq!(stray.unbalanced[0].offset, 2);
+    }
+
+    #[test]
+    fn test_unit_errors_carry_the_unit_index() {
+        let input = "7\n00:00:01,000 --> 00:00:02,000\n（狡噛\nはい\n";
+        let units = SubtitleParser::new().read_str(input).unwrap();
+
+        let error = remove_unit_parentheses(&units[0], ParenthesisPolicy::Fail).unwrap_err();
+        assert_eq!(error.unit_index, Some(7));
+        assert_eq!(
+            error.to_string(),
+            "Unclosed parenthesis （ at character 0 of unit 7"
+        );
+
+        let episode = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        for unit in &episode {
+            assert!(remove_unit_parentheses(unit, ParenthesisPolicy::Fail).is_ok());
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use crate::dataprep::ass::{self, AssError, read_ass_str};
+use crate::dataprep::ingestion::{PathError, SafeFilePath, decode_subtitle_bytes};
+use crate::dataprep::microdvd::{MicroDvdError, read_microdvd_str};
+use crate::dataprep::parser::{SubtitleParser, SubtitleParserError};
+use crate::dataprep::subviewer::{SubViewerError, read_subviewer_str};
+use crate::dataprep::ttml::{TtmlError, read_ttml_str};
+use crate::dataprep::webvtt::{WebVttError, read_webvtt_str};
+use crate::types::framerate::Framerate;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timing::Timing;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+
+/// How many bytes at the start of a source are looked at to guess its format.
+pub const FORMAT_SNIFF_WINDOW: usize = 4 * 1024;
+
+/// Guesses below this confidence are treated as no match.
+pub const MIN_CONFIDENCE: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+    /// Advanced SubStation Alpha, and SubStation Alpha before it.
+    Ass,
+    /// TTML, including the IMSC and DFXP profiles.
+    Ttml,
+    MicroDvd,
+    /// SubViewer 2.0, with its `[INFORMATION]` header.
+    SubViewer,
+    /// YouTube’s SBV: SubViewer timing lines with no header.
+    Sbv,
+}
+
+/// A format and how sure [`score_formats`] is of it, from 0.0 to 1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FormatGuess {
+    pub format: SubtitleFormat,
+    pub confidence: f32,
+}
+
+#[derive(Debug)]
+pub enum SubtitleReadError {
+    Io(io::Error),
+    Path(PathError),
+    /// No format reached [`MIN_CONFIDENCE`].
+    UnknownFormat,
+    Srt(SubtitleParserError),
+    WebVtt(WebVttError),
+    Ass(AssError),
+    Ttml(TtmlError),
+    MicroDvd(MicroDvdError),
+    SubViewer(SubViewerError),
+}
+
+impl Display for SubtitleReadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubtitleReadError::Io(e) => write!(f, "I/O error: {}", e),
+            SubtitleReadError::Path(e) => write!(f, "Path error: {}", e),
+            SubtitleReadError::UnknownFormat => {
+                write!(f, "Content doesn’t look like any supported subtitle format")
+            }
+            SubtitleReadError::Srt(e) => write!(f, "SRT error: {}", e),
+            SubtitleReadError::WebVtt(e) => write!(f, "WebVTT error: {}", e),
+            SubtitleReadError::Ass(e) => write!(f, "ASS error: {}", e),
+            SubtitleReadError::Ttml(e) => write!(f, "TTML error: {}", e),
+            SubtitleReadError::MicroDvd(e) => write!(f, "MicroDVD error: {}", e),
+            SubtitleReadError::SubViewer(e) => write!(f, "SubViewer error: {}", e),
+        }
+    }
+}
+
+impl Error for SubtitleReadError {}
+
+impl From<io::Error> for SubtitleReadError {
+    fn from(error: io::Error) -> Self {
+        SubtitleReadError::Io(error)
+    }
+}
+
+impl From<PathError> for SubtitleReadError {
+    fn from(error: PathError) -> Self {
+        SubtitleReadError::Path(error)
+    }
+}
+
+impl From<SubtitleParserError> for SubtitleReadError {
+    fn from(error: SubtitleParserError) -> Self {
+        SubtitleReadError::Srt(error)
+    }
+}
+
+impl From<WebVttError> for SubtitleReadError {
+    fn from(error: WebVttError) -> Self {
+        SubtitleReadError::WebVtt(error)
+    }
+}
+
+impl From<AssError> for SubtitleReadError {
+    fn from(error: AssError) -> Self {
+        SubtitleReadError::Ass(error)
+    }
+}
+
+impl From<TtmlError> for SubtitleReadError {
+    fn from(error: TtmlError) -> Self {
+        SubtitleReadError::Ttml(error)
+    }
+}
+
+impl From<MicroDvdError> for SubtitleReadError {
+    fn from(error: MicroDvdError) -> Self {
+        SubtitleReadError::MicroDvd(error)
+    }
+}
+
+impl From<SubViewerError> for SubtitleReadError {
+    fn from(error: SubViewerError) -> Self {
+        SubtitleReadError::SubViewer(error)
+    }
+}
+
+pub fn score_formats(prefix: &str) -> Vec<FormatGuess> {
+    //! Scores `prefix`, the start of a decoded source, against every format and
+    //! returns those with a non-zero score, most likely first.
+    //!
+    //! Headers (`WEBVTT`, `[Script Info]`, `<tt>`, `[INFORMATION]`) are near
+    //! certain. Formats without one are scored by how many blocks or lines
+    //! have the right shape, so a file with the odd broken block still scores
+    //! well.
+
+    let prefix = prefix.trim_start_matches('\u{FEFF}');
+    let lines: Vec<&str> = prefix.lines().map(str::trim_end).collect();
+    let first = lines
+        .iter()
+        .find(|line| !line.is_empty())
+        .copied()
+        .unwrap_or_default();
+    let non_blank = lines.iter().filter(|line| !line.is_empty()).count().max(1) as f32;
+    // Blocks are runs of non-blank lines.
+    let blocks = lines
+        .split(|line| line.is_empty())
+        .filter(|block| !block.is_empty())
+        .count()
+        .max(1) as f32;
+    let contains_line = |wanted: &str| lines.iter().any(|line| line.trim() == wanted);
+
+    // SRT: timing lines, preferably straight after an index.
+    let mut srt_timings = 0;
+    let mut srt_indexed_timings = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if line.parse::<Timing>().is_ok() {
+            srt_timings += 1;
+            if i > 0 && lines[i - 1].parse::<u32>().is_ok() {
+                srt_indexed_timings += 1;
+            }
+        }
+    }
+    let srt = (0.5 * srt_timings as f32 + 0.45 * srt_indexed_timings as f32) / blocks;
+
+    let webvtt = if first
+        .strip_prefix("WEBVTT")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+    {
+        1.0
+    } else {
+        // Without the signature the WebVTT reader can’t read the file, so cue
+        // timings alone aren’t a guess worth making.
+        0.0
+    };
+
+    let ass = if first.eq_ignore_ascii_case("[Script Info]") {
+        1.0
+    } else if ["[V4+ Styles]", "[V4 Styles]", "[Events]"]
+        .into_iter()
+        .any(contains_line)
+    {
+        0.8
+    } else if lines.iter().any(|line| line.starts_with("Dialogue:")) {
+        0.5
+    } else {
+        0.0
+    };
+
+    let ttml = if !prefix.contains("<tt") {
+        0.0
+    } else if prefix.contains("http://www.w3.org/ns/ttml") || prefix.contains("ttaf1") {
+        1.0
+    } else if prefix.contains("<p") {
+        0.6
+    } else {
+        0.3
+    };
+
+    let microdvd_lines = lines.iter().filter(|line| is_microdvd_line(line)).count();
+    let microdvd = 0.95 * microdvd_lines as f32 / non_blank;
+
+    // SubViewer and SBV share their timing lines; the header tells them apart.
+    let subviewer_timings = lines
+        .iter()
+        .filter(|line| is_subviewer_timing(line))
+        .count();
+    let has_subviewer_header = contains_line("[INFORMATION]") || contains_line("[SUBTITLE]");
+    let (subviewer, sbv) = match (has_subviewer_header, subviewer_timings) {
+        (true, 0) => (0.6, 0.0),
+        (true, _) => (1.0, 0.0),
+        (false, timings) => (0.0, 0.9 * timings as f32 / blocks),
+    };
+
+    let mut guesses: Vec<FormatGuess> = [
+        (SubtitleFormat::Srt, srt),
+        (SubtitleFormat::WebVtt, webvtt),
+        (SubtitleFormat::Ass, ass),
+        (SubtitleFormat::Ttml, ttml),
+        (SubtitleFormat::MicroDvd, microdvd),
+        (SubtitleFormat::SubViewer, subviewer),
+        (SubtitleFormat::Sbv, sbv),
+    ]
+    .into_iter()
+    .filter(|&(_, confidence)| confidence > 0.0)
+    .map(|(format, confidence)| FormatGuess {
+        format,
+        confidence: confidence.min(1.0),
+    })
+    .collect();
+    // Stable, so ties keep the order above.
+    guesses.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    guesses
+}
+
+pub fn detect_format(prefix: &str) -> Option<FormatGuess> {
+    //! The most likely format of `prefix`, if any reaches [`MIN_CONFIDENCE`].
+
+    score_formats(prefix)
+        .into_iter()
+        .next()
+        .filter(|guess| guess.confidence >= MIN_CONFIDENCE)
+}
+
+pub fn sniff_format(bytes: &[u8]) -> Option<FormatGuess> {
+    //! Decodes the first [`FORMAT_SNIFF_WINDOW`] bytes of a source (in whatever
+    //! encoding it seems to be in) and detects its format from them.
+
+    let window = &bytes[..bytes.len().min(FORMAT_SNIFF_WINDOW)];
+    let (mut prefix, _) = decode_subtitle_bytes(window, None);
+
+    // The window probably ends mid-line; a partial line would only mislead.
+    if window.len() < bytes.len()
+        && let Some(last_newline) = prefix.rfind('\n')
+    {
+        prefix.truncate(last_newline);
+    }
+
+    detect_format(&prefix)
+}
+
+pub fn read_subtitle_bytes(
+    bytes: &[u8],
+    fps: Framerate,
+) -> Result<(FormatGuess, Vec<SubtitleUnit>), SubtitleReadError> {
+    //! Detects the format of a whole source and parses it with the matching
+    //! reader. `fps` is only used for MicroDVD files that don’t declare their
+    //! own framerate.
+
+    let guess = sniff_format(bytes).ok_or(SubtitleReadError::UnknownFormat)?;
+
+    // The SRT parser does its own encoding detection; the other readers take text.
+    let text = || decode_subtitle_bytes(bytes, None).0;
+    let units = match guess.format {
+        SubtitleFormat::Srt => SubtitleParser::new()
+            .parse(Cursor::new(bytes))?
+            .collect::<Result<_, _>>()?,
+        SubtitleFormat::WebVtt => read_webvtt_str(&text())?.units(),
+        SubtitleFormat::Ass => read_ass_str(&text())?.units(),
+        SubtitleFormat::Ttml => read_ttml_str(&text())?.units(),
+        SubtitleFormat::MicroDvd => read_microdvd_str(&text(), fps)?,
+        SubtitleFormat::SubViewer | SubtitleFormat::Sbv => read_subviewer_str(&text())?,
+    };
+
+    Ok((guess, units))
+}
+
+pub fn read_subtitle_file(
+    path: impl AsRef<Path>,
+    fps: Framerate,
+) -> Result<(FormatGuess, Vec<SubtitleUnit>), SubtitleReadError> {
+    //! Like [`read_subtitle_bytes`], for a file with any extension.
+
+    // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::try_from_any_extension(raw_path)?;
+
+    read_subtitle_bytes(&fs::read(checked_path)?, fps)
+}
+
+/// `{start}{end}` at the start of the line, where `end` may be empty.
+fn is_microdvd_line(line: &str) -> bool {
+    let digits = |field: &str| field.chars().all(|c| c.is_ascii_digit());
+    let Some((start, rest)) = line.strip_prefix('{').and_then(|l| l.split_once('}')) else {
+        return false;
+    };
+    let Some((end, _)) = rest.strip_prefix('{').and_then(|r| r.split_once('}')) else {
+        return false;
+    };
+
+    !start.is_empty() && digits(start) && digits(end)
+}
+
+/// `h:mm:ss.cc,h:mm:ss.cc`, with one to three digits after each `.`.
+fn is_subviewer_timing(line: &str) -> bool {
+    line.split_once(',').is_some_and(|(start, end)| {
+        ass::parse_timestamp(start).is_ok() && ass::parse_timestamp(end).is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::ass::to_ass_string;
+    use crate::dataprep::microdvd::to_microdvd_string;
+    use crate::dataprep::subviewer::to_subviewer_string;
+    use crate::dataprep::webvtt::to_webvtt_string;
+
+    const EPISODE_PATH: &str = "data/raw/psycho-pass-s01e01-jp.srt";
+
+    #[test]
+    fn test_every_format_is_detected_and_read() {
+        let units = SubtitleParser::new().read_file(EPISODE_PATH).unwrap();
+        let fps = Framerate::NTSC_FILM;
+        let sbv = "0:00:12.846,0:00:24.563\n犯罪係数\n\n0:00:25.000,0:00:26.000\n測定中\n";
+        let ttml = r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><div>
+            <p begin="1s" end="2s">Hi</p></div></body></tt>"#;
+
+        let sources = [
+            (SubtitleFormat::Srt, fs::read(EPISODE_PATH).unwrap()),
+            (
+                SubtitleFormat::WebVtt,
+                to_webvtt_string(&units).into_bytes(),
+            ),
+            (SubtitleFormat::Ass, to_ass_string(&units).into_bytes()),
+            (SubtitleFormat::Ttml, ttml.as_bytes().to_vec()),
+            (
+                SubtitleFormat::MicroDvd,
+                to_microdvd_string(&units, fps).into_bytes(),
+            ),
+            (
+                SubtitleFormat::SubViewer,
+                to_subviewer_string(&units).into_bytes(),
+            ),
+            (SubtitleFormat::Sbv, sbv.as_bytes().to_vec()),
+        ];
+
+        for (format, bytes) in sources {
+            let (guess, read) = read_subtitle_bytes(&bytes, fps).unwrap();
+            assert_eq!(guess.format, format);
+            assert!(guess.confidence >= 0.9, "{:?}", guess);
+            assert!(!read.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_scores_rank_every_candidate() {
+        // SBV-shaped lines also fit SubViewer, but without the header.
+        let guesses = score_formats("0:00:01.000,0:00:02.000\nHi\n");
+        assert_eq!(guesses[0].format, SubtitleFormat::Sbv);
+
+        // WebVTT cue timings without the signature aren’t readable as anything.
+        assert!(
+            score_formats("00:01.000 --> 00:02.000\nHi\n")
+                .iter()
+                .all(|guess| guess.format != SubtitleFormat::WebVtt)
+        );
+        assert!(matches!(
+            read_subtitle_bytes(b"00:00:01.000 --> 00:00:02.000\nA\n", Framerate::NTSC_FILM),
+            Err(SubtitleReadError::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn test_any_extension_is_sniffed() {
+        // Any extension gets past the path checks; the content decides.
+        assert!(matches!(
+            read_subtitle_file("Cargo.toml", Framerate::NTSC_FILM),
+            Err(SubtitleReadError::UnknownFormat)
+        ));
+    }
+}
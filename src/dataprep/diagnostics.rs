@@ -0,0 +1,134 @@
+use crate::dataprep::parser::SubtitleParserError;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// A position in the parsed source. `line` and `column` are 1-based, and
+/// `column` counts characters rather than bytes; `byte_offset` is 0-based and
+/// counts from the very start of the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub byte_offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where a parse error happened, plus enough of the source to show it.
+///
+/// `snippet` holds every line of the offending block, the first of which is
+/// line `snippet_first_line` of the source. The problem itself starts at
+/// `location` and runs for `span` characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub location: SourceLocation,
+    pub span: usize,
+    /// Index of the unit the error belongs to, if it got far enough to be read.
+    pub unit_index: Option<u32>,
+    pub snippet_first_line: usize,
+    pub snippet: Vec<String>,
+}
+
+pub fn render_diagnostic(error: &SubtitleParserError) -> String {
+    //! Renders an error the way compiler errors look: a headline, the location,
+    //! and the offending block with a caret underline beneath the bad span.
+    //!
+    //! ```text
+    //! error: Timing parsing error at line 6, column 18: ...
+    //!   --> line 6, column 18 (byte offset 79), unit 2
+    //!    |
+    //!  5 | 2
+    //!  6 | 00:00:46,921 --> 00:00:4x,839
+    //!    |                  ^^^^^^^^^^^^
+    //!  7 | （狡噛）フゥ～…
+    //! ```
+    //!
+    //! Errors without a location (IO and path errors) render as the headline only.
+    //!
+    //! Carets are aligned by character count, so they drift to the left under
+    //! full-width characters. This is fine for index and timing lines, which are
+    //! where nearly all errors point.
+
+    let mut rendered = format!("error: {}\n", error);
+
+    let Some(context) = error.context() else {
+        return rendered;
+    };
+
+    let last_line = context.snippet_first_line + context.snippet.len().saturating_sub(1);
+    let gutter_width = last_line.to_string().len();
+    let location = &context.location;
+
+    let unit = match context.unit_index {
+        Some(index) => format!(", unit {}", index),
+        None => String::new(),
+    };
+    // Writing to a `String` can’t fail, so the results below are ignored.
+    let _ = writeln!(
+        rendered,
+        "{:width$}--> line {}, column {} (byte offset {}){}",
+        "",
+        location.line,
+        location.column,
+        location.byte_offset,
+        unit,
+        width = gutter_width + 1
+    );
+    let _ = writeln!(rendered, "{:width$} |", "", width = gutter_width);
+
+    for (i, line) in context.snippet.iter().enumerate() {
+        let line_number = context.snippet_first_line + i;
+        let _ = writeln!(
+            rendered,
+            "{:>width$} | {}",
+            line_number,
+            line,
+            width = gutter_width
+        );
+
+        if line_number == location.line {
+            let _ = writeln!(
+                rendered,
+                "{:width$} | {}{}",
+                "",
+                " ".repeat(location.column.saturating_sub(1)),
+                "^".repeat(context.span.max(1)),
+                width = gutter_width
+            );
+        }
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_render_points_at_bad_timestamp() {
+        let input =
+            "1\n00:00:01,000 --> 00:00:02,000\nHello\n\n2\n00:00:03,000 --> 00:0x:04,000\nWorld\n";
+        let error = SubtitleParser::new()
+            .parse_str(input)
+            .unwrap()
+            .find_map(Result::err)
+            .unwrap();
+
+        let context = error.context().unwrap();
+        assert_eq!(context.location.line, 6);
+        assert_eq!(context.location.column, 18);
+        assert_eq!(context.location.byte_offset, 58);
+        assert_eq!(context.unit_index, Some(2));
+
+        let rendered = render_diagnostic(&error);
+        let expected_tail = "\
+ --> line 6, column 18 (byte offset 58), unit 2
+  |
+5 | 2
+6 | 00:00:03,000 --> 00:0x:04,000
+  |                  ^^^^^^^^^^^^
+7 | World
+";
+        assert!(rendered.ends_with(expected_tail), "{}", rendered);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::types::segment::Segment;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::Timestamp;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// One word the subtitler glossed with furigana, e.g. 狡噛 read こうがみ. The
+/// same kanji with two different readings gives two entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GlossaryEntry {
+    pub base: String,
+    pub reading: String,
+    /// When the glossed word first appears on screen.
+    pub first_seen: Timestamp,
+    pub first_unit: u32,
+    pub occurrences: usize,
+}
+
+/// Every furigana pair in an episode, in order of first appearance. Subtitlers
+/// usually gloss a name or rare word only the first time it comes up, so most
+/// entries have one occurrence.
+///
+/// Serialises to JSON as a list of [`GlossaryEntry`]s, e.g. via
+/// [`ReadingGlossary::to_json`], or to CSV via [`ReadingGlossary::write_csv`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ReadingGlossary {
+    entries: Vec<GlossaryEntry>,
+}
+
+impl ReadingGlossary {
+    pub fn entries(&self) -> &[GlossaryEntry] {
+        &self.entries
+    }
+
+    /// The readings given for `base`, in order of first appearance.
+    pub fn readings_of(&self, base: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.base == base)
+            .map(|entry| entry.reading.as_str())
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Writes a header row and one row per entry.
+    pub fn write_csv<W: Write>(&self, destination: W) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(destination);
+        for entry in &self.entries {
+            writer.serialize(entry)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+pub fn harvest_furigana(units: &[SubtitleUnit]) -> ReadingGlossary {
+    //! Collects every `kanji(kana)` pair in `units`, including those inside
+    //! speaker labels and sound-effect captions.
+
+    let mut glossary = ReadingGlossary::default();
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+
+    for unit in units {
+        let mut pairs = Vec::new();
+        collect_ruby(&unit.segments(), &mut pairs);
+
+        for (base, reading) in pairs {
+            let key = (base.clone(), reading.clone());
+            let position = *positions.entry(key).or_insert_with(|| {
+                glossary.entries.push(GlossaryEntry {
+                    base,
+                    reading,
+                    first_seen: unit.timing.start,
+                    first_unit: unit.index.value(),
+                    occurrences: 0,
+                });
+                glossary.entries.len() - 1
+            });
+            glossary.entries[position].occurrences += 1;
+        }
+    }
+
+    glossary
+}
+
+fn collect_ruby(segments: &[Segment], pairs: &mut Vec<(String, String)>) {
+    for segment in segments {
+        match segment {
+            Segment::Ruby { base, reading } => pairs.push((base.clone(), reading.clone())),
+            Segment::Speaker(inner) | Segment::SoundEffect(inner) => collect_ruby(inner, pairs),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_harvest_furigana() {
+        let input = "1\n00:00:01,000 --> 00:00:02,000\n（狡噛(こうがみ)）\n槙島 聖護(しょうご)\n\n\
+            2\n00:00:03,000 --> 00:00:04,000\n狡噛(こうがみ)だ\n";
+        let units = SubtitleParser::new().read_str(input).unwrap();
+        let glossary = harvest_furigana(&units);
+
+        assert_eq!(glossary.entries().len(), 2);
+        assert_eq!(glossary.entries()[0].occurrences, 2);
+        assert_eq!(glossary.entries()[0].first_seen.to_string(), "00:00:01,000");
+        assert_eq!(glossary.readings_of("聖護"), vec!["しょうご"]);
+
+        let mut csv = Vec::new();
+        glossary.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "base,reading,first_seen,first_unit,occurrences\n\
+            狡噛,こうがみ,\"00:00:01,000\",1,2\n\
+            聖護,しょうご,\"00:00:01,000\",1,1\n"
+        );
+
+        let json = glossary.to_json().unwrap();
+        assert!(json.starts_with('['));
+        assert!(json.contains("\"first_seen\": \"00:00:01,000\""));
+    }
+
+    #[test]
+    fn test_episode_glossary() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let glossary = harvest_furigana(&units);
+
+        assert_eq!(glossary.readings_of("狡噛"), vec!["こうがみ"]);
+        assert_eq!(glossary.readings_of("朱"), vec!["あかね"]);
+        assert_eq!(glossary.entries()[0].first_unit, 2);
+    }
+}
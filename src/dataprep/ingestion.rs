@@ -3,6 +3,10 @@
 #![allow(unused_variables)]
 
 use anyhow::Result;
+use encoding_rs::{
+    CoderResult, Decoder, DecoderResult, EUC_JP, Encoding, SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE,
+};
+use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_json::from_reader;
 use std::convert::TryFrom;
@@ -11,7 +15,8 @@ use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io;
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
 
 const PATH_CHAR_WHITELIST: &str =
@@ -22,7 +27,10 @@ pub enum PathError {
     EmptyPath,
     FileNotFound,
     IllegalCharacters,
-    IncorrectExtension,
+    /// The file’s extension isn’t one of `expected`.
+    IncorrectExtension {
+        expected: &'static [&'static str],
+    },
 }
 
 impl Display for PathError {
@@ -37,7 +45,12 @@ impl Display for PathError {
                 f,
                 "Characters must be:\n ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz01234567890-./"
             ),
-            PathError::IncorrectExtension => write!(f, "File extension is not .srt"),
+            PathError::IncorrectExtension { expected: [only] } => {
+                write!(f, "File extension is not .{}", only)
+            }
+            PathError::IncorrectExtension { expected } => {
+                write!(f, "File extension must be one of .{}", expected.join(", ."))
+            }
         }
     }
 }
@@ -54,16 +67,49 @@ pub struct SafeFilePath {
 impl TryFrom<&str> for SafeFilePath {
     type Error = PathError;
 
-    /// Returns the **absolute** file path wrapped in a `Result` type.
+    /// Returns the **absolute** file path wrapped in a `Result` type. The file
+    /// must be an SRT file; see [`SafeFilePath::with_extensions`] for others.
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        SafeFilePath::with_extensions(value, &["srt"])
+    }
+}
+
+impl SafeFilePath {
+    /// Like [`SafeFilePath::try_from`], but accepting any of `extensions`
+    /// (without the dot) instead of only `.srt`.
+    pub fn with_extensions(
+        value: &str,
+        extensions: &'static [&'static str],
+    ) -> Result<Self, PathError> {
         let path = PathBuf::from(value);
 
         if value.trim().is_empty() {
             return Err(PathError::EmptyPath);
-        } else if value.chars().all(|char| PATH_CHAR_WHITELIST.contains(char)) == false {
+        } else if !value.chars().all(|char| PATH_CHAR_WHITELIST.contains(char)) {
+            return Err(PathError::IllegalCharacters);
+        } else if !extensions
+            .iter()
+            .any(|&extension| path.extension() == Some(extension.as_ref()))
+        {
+            return Err(PathError::IncorrectExtension {
+                expected: extensions,
+            });
+        };
+
+        SafeFilePath::try_from_any_extension(value)
+    }
+
+    /// Like [`SafeFilePath::try_from`], but with no check on the extension, for
+    /// files whose format is worked out from their content (see
+    /// [`detection`](crate::dataprep::detection)). Subtitles from some sources
+    /// come as `.txt` or with no extension at all.
+    pub fn try_from_any_extension(value: &str) -> Result<Self, PathError> {
+        let path = PathBuf::from(value);
+
+        if value.trim().is_empty() {
+            return Err(PathError::EmptyPath);
+        } else if !value.chars().all(|char| PATH_CHAR_WHITELIST.contains(char)) {
             return Err(PathError::IllegalCharacters);
-        } else if path.extension() != Some("srt".as_ref()) {
-            return Err(PathError::IncorrectExtension);
         };
 
         let result: Result<SafeFilePath, PathError> = match fs::canonicalize(&path) {
@@ -100,3 +146,261 @@ where
 
     Ok(data)
 }
+
+/// How many bytes at the start of a source are looked at to guess its encoding.
+/// An SRT file for a whole episode usually fits in this comfortably.
+pub const ENCODING_SNIFF_WINDOW: usize = 64 * 1024;
+
+/// Text encodings that subtitle files are known to turn up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SubtitleEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    ShiftJis,
+    EucJp,
+}
+
+impl SubtitleEncoding {
+    fn encoding(self) -> &'static Encoding {
+        match self {
+            SubtitleEncoding::Utf8 => UTF_8,
+            SubtitleEncoding::Utf16Le => UTF_16LE,
+            SubtitleEncoding::Utf16Be => UTF_16BE,
+            // encoding_rs’s Shift_JIS is the WHATWG superset, i.e. Windows-31J.
+            SubtitleEncoding::ShiftJis => SHIFT_JIS,
+            SubtitleEncoding::EucJp => EUC_JP,
+        }
+    }
+}
+
+/// How the encoding of a source was decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EncodingSource {
+    /// Set explicitly by the caller.
+    Override,
+    /// Taken from a UTF-8 or UTF-16 byte-order mark.
+    ByteOrderMark,
+    /// Guessed from the content; see [`detect_encoding`].
+    Content,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DetectedEncoding {
+    pub encoding: SubtitleEncoding,
+    pub source: EncodingSource,
+}
+
+pub fn detect_encoding(prefix: &[u8]) -> DetectedEncoding {
+    //! Guesses the encoding of a subtitle source from its first bytes (up to
+    //! [`ENCODING_SNIFF_WINDOW`] is plenty).
+    //!
+    //! - A byte-order mark settles it.
+    //! - Failing that, lots of zero bytes in odd or even positions mean UTF-16.
+    //! - Otherwise, the prefix is decoded as UTF-8, Shift_JIS and EUC-JP, and the
+    //!   encoding with the fewest malformed sequences wins. Ties go to the one
+    //!   that yields more kana, then to the order just given; pure ASCII is
+    //!   therefore UTF-8.
+    //!
+    //! Only the prefix is looked at, so a file whose first 64 KiB are ASCII is
+    //! taken to be UTF-8 even if legacy bytes turn up further on.
+
+    let by_content = |encoding| DetectedEncoding {
+        encoding,
+        source: EncodingSource::Content,
+    };
+
+    if let Some((encoding, _)) = Encoding::for_bom(prefix) {
+        let encoding = if encoding == UTF_16LE {
+            SubtitleEncoding::Utf16Le
+        } else if encoding == UTF_16BE {
+            SubtitleEncoding::Utf16Be
+        } else {
+            SubtitleEncoding::Utf8
+        };
+        return DetectedEncoding {
+            encoding,
+            source: EncodingSource::ByteOrderMark,
+        };
+    }
+
+    // ASCII text in UTF-16 has a zero byte in every other position; Japanese
+    // text still has plenty of them (punctuation, digits, line endings).
+    let zeros_at = |parity: usize| {
+        prefix
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&byte| byte == 0)
+            .count()
+    };
+    let half_len = prefix.len() / 2;
+    if half_len > 0 && zeros_at(1) * 4 >= half_len {
+        return by_content(SubtitleEncoding::Utf16Le);
+    } else if half_len > 0 && zeros_at(0) * 4 >= half_len {
+        return by_content(SubtitleEncoding::Utf16Be);
+    }
+
+    // A prefix cut off mid-character would count as malformed, so only whole
+    // lines are compared. `\n` never occurs inside a Shift_JIS or EUC-JP
+    // multi-byte character.
+    let whole_lines = match prefix.iter().rposition(|&byte| byte == b'\n') {
+        Some(last_newline) => &prefix[..=last_newline],
+        None => prefix,
+    };
+
+    let candidates = [
+        SubtitleEncoding::Utf8,
+        SubtitleEncoding::ShiftJis,
+        SubtitleEncoding::EucJp,
+    ];
+    let best = candidates
+        .into_iter()
+        .enumerate()
+        .min_by_key(|&(order, candidate)| {
+            let (malformed, kana) = decoding_score(candidate.encoding(), whole_lines);
+            (malformed, usize::MAX - kana, order)
+        })
+        .map(|(_, candidate)| candidate)
+        .unwrap_or(SubtitleEncoding::Utf8);
+
+    by_content(best)
+}
+
+/// Decodes `bytes` and returns the number of malformed sequences and the
+/// number of hiragana and katakana characters in the output.
+fn decoding_score(encoding: &'static Encoding, bytes: &[u8]) -> (usize, usize) {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut output = String::with_capacity(bytes.len() * 3);
+    let mut remaining = bytes;
+    let mut malformed = 0;
+
+    loop {
+        let (result, read) =
+            decoder.decode_to_string_without_replacement(remaining, &mut output, true);
+        remaining = &remaining[read..];
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::Malformed(_, _) => malformed += 1,
+            DecoderResult::OutputFull => output.reserve(remaining.len() * 3 + 16),
+        }
+    }
+
+    let kana = output
+        .chars()
+        .filter(|c| matches!(c, '\u{3040}'..='\u{30FF}'))
+        .count();
+
+    (malformed, kana)
+}
+
+/// A `BufRead` adapter that transcodes its source to UTF-8 on the fly, so the
+/// parser only ever sees UTF-8. Sources that are already UTF-8 are passed
+/// through untouched (and invalid bytes in them still fail as usual).
+///
+/// A byte-order mark is kept, as `U+FEFF`, for the parser’s normalisation step
+/// to remove and report. Malformed sequences in legacy encodings become
+/// `U+FFFD`.
+pub struct TranscodingReader<R: BufRead> {
+    inner: R,
+    decoder: Option<Decoder>,
+    decoded: String,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> TranscodingReader<R> {
+    pub fn new(inner: R, encoding: SubtitleEncoding) -> Self {
+        let decoder = match encoding {
+            SubtitleEncoding::Utf8 => None,
+            _ => Some(encoding.encoding().new_decoder_without_bom_handling()),
+        };
+
+        TranscodingReader {
+            inner,
+            decoder,
+            decoded: String::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+}
+
+// `Decoder` doesn’t implement `Debug`, so the derive isn’t available.
+impl<R: BufRead> std::fmt::Debug for TranscodingReader<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TranscodingReader")
+            .field("transcoding", &self.decoder.is_some())
+            .field("position", &self.position)
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
+impl<R: BufRead> Read for TranscodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for TranscodingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let Some(decoder) = self.decoder.as_mut() else {
+            return self.inner.fill_buf();
+        };
+
+        // The decoder can swallow input without producing output (e.g. half of
+        // a multi-byte character), so keep going until there is some.
+        while self.position >= self.decoded.len() && !self.finished {
+            self.decoded.clear();
+            self.position = 0;
+
+            let source = self.inner.fill_buf()?;
+            let last = source.is_empty();
+            let capacity = decoder
+                .max_utf8_buffer_length(source.len())
+                .unwrap_or(source.len() * 3 + 16);
+            self.decoded.reserve(capacity);
+
+            let (result, read, _) = decoder.decode_to_string(source, &mut self.decoded, last);
+            self.inner.consume(read);
+            self.finished = last && result == CoderResult::InputEmpty;
+        }
+
+        Ok(&self.decoded.as_bytes()[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        match self.decoder {
+            None => self.inner.consume(amount),
+            Some(_) => self.position = (self.position + amount).min(self.decoded.len()),
+        }
+    }
+}
+
+pub fn decode_subtitle_bytes(
+    bytes: &[u8],
+    encoding_override: Option<SubtitleEncoding>,
+) -> (String, DetectedEncoding) {
+    //! Decodes a whole subtitle file held in memory, using `encoding_override`
+    //! if given and [`detect_encoding`] otherwise. Malformed sequences become
+    //! `U+FFFD`; a byte-order mark is kept as `U+FEFF`.
+
+    let detected = match encoding_override {
+        Some(encoding) => DetectedEncoding {
+            encoding,
+            source: EncodingSource::Override,
+        },
+        None => detect_encoding(&bytes[..bytes.len().min(ENCODING_SNIFF_WINDOW)]),
+    };
+    let (decoded, _) = detected
+        .encoding
+        .encoding()
+        .decode_without_bom_handling(bytes);
+
+    (decoded.into_owned(), detected)
+}
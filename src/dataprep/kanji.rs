@@ -0,0 +1,228 @@
+use crate::types::segment::{is_ideograph, spoken_text};
+use crate::types::subtitle_unit::SubtitleUnit;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+/// `data/raw/joyo_kanji.txt`: the 2,136 _jōyō_ kanji of the 2010 list.
+const JOYO_KANJI: &str = include_str!("../../data/raw/joyo_kanji.txt");
+/// `data/raw/kyoiku_kanji.json`: the 1,026 _kyōiku_ kanji, keyed by the school
+/// grade (1 to 6) that teaches them.
+const KYOIKU_KANJI_JSON: &str = include_str!("../../data/raw/kyoiku_kanji.json");
+/// `data/raw/jinmeiyo_kanji.txt`: the _jinmeiyō_ kanji, followed by the
+/// traditional forms of _jōyō_ kanji that are allowed in names.
+const JINMEIYO_KANJI: &str = include_str!("../../data/raw/jinmeiyo_kanji.txt");
+
+static JOYO: LazyLock<HashSet<char>> = LazyLock::new(|| read_list(JOYO_KANJI));
+
+static KYOIKU_GRADES: LazyLock<HashMap<char, u8>> = LazyLock::new(|| {
+    let grades: HashMap<u8, String> =
+        serde_json::from_str(KYOIKU_KANJI_JSON).expect("The bundled kyōiku kanji are valid JSON");
+    grades
+        .iter()
+        .flat_map(|(grade, kanji)| kanji.chars().map(|c| (c, *grade)))
+        .collect()
+});
+
+static JINMEIYO: LazyLock<HashSet<char>> = LazyLock::new(|| read_list(JINMEIYO_KANJI));
+
+/// Which official list a kanji belongs to. The lists don’t overlap, except that
+/// every _kyōiku_ kanji is also _jōyō_; such kanji are
+/// [`Kyoiku`](KanjiCategory::Kyoiku) rather than [`Joyo`](KanjiCategory::Joyo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KanjiCategory {
+    /// Taught in primary school, in `grade` 1 to 6.
+    Kyoiku { grade: u8 },
+    /// _Jōyō_ but taught only after primary school.
+    Joyo,
+    /// Allowed in personal names but not _jōyō_.
+    Jinmeiyo,
+    /// On neither the _jōyō_ nor the _jinmeiyō_ list.
+    Hyogai,
+}
+
+impl KanjiCategory {
+    pub fn is_joyo(self) -> bool {
+        matches!(self, KanjiCategory::Kyoiku { .. } | KanjiCategory::Joyo)
+    }
+}
+
+/// How often one kanji occurs, for [`KanjiReport::hyogai_kanji`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KanjiCount {
+    pub kanji: char,
+    pub occurrences: usize,
+}
+
+/// Kanji in an episode’s dialogue, counted by [`KanjiCategory`]. Every count is
+/// of occurrences, not of distinct kanji, unless its name says otherwise.
+///
+/// Serialises to JSON with serde, e.g. via [`KanjiReport::to_json`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct KanjiReport {
+    pub kanji: usize,
+    pub distinct_kanji: usize,
+    /// _Kyōiku_ kanji per school grade, grade 1 first.
+    pub kyoiku_by_grade: [usize; 6],
+    /// _Jōyō_ kanji outside the _kyōiku_ list.
+    pub secondary_joyo: usize,
+    pub jinmeiyo: usize,
+    pub hyogai: usize,
+    /// The share of kanji that are _hyōgai_, from `0.0` to `1.0`; `0.0` if
+    /// there are no kanji at all. The README suggests this as a rough measure
+    /// of how adult-orientated a series is.
+    pub hyogai_ratio: f64,
+    /// Every _hyōgai_ kanji, most frequent first.
+    pub hyogai_kanji: Vec<KanjiCount>,
+}
+
+impl KanjiReport {
+    pub fn from_text(text: &str) -> Self {
+        let mut report = KanjiReport::default();
+        let mut seen: HashSet<char> = HashSet::new();
+        let mut hyogai: HashMap<char, usize> = HashMap::new();
+
+        for c in text.chars() {
+            let Some(category) = kanji_category(c) else {
+                continue;
+            };
+            report.kanji += 1;
+            seen.insert(c);
+            match category {
+                KanjiCategory::Kyoiku { grade } => report.kyoiku_by_grade[grade as usize - 1] += 1,
+                KanjiCategory::Joyo => report.secondary_joyo += 1,
+                KanjiCategory::Jinmeiyo => report.jinmeiyo += 1,
+                KanjiCategory::Hyogai => {
+                    report.hyogai += 1;
+                    *hyogai.entry(c).or_default() += 1;
+                }
+            }
+        }
+
+        report.distinct_kanji = seen.len();
+        if report.kanji > 0 {
+            report.hyogai_ratio = report.hyogai as f64 / report.kanji as f64;
+        }
+        report.hyogai_kanji = hyogai
+            .into_iter()
+            .map(|(kanji, occurrences)| KanjiCount { kanji, occurrences })
+            .collect();
+        report
+            .hyogai_kanji
+            .sort_by_key(|count| (Reverse(count.occurrences), count.kanji));
+        report
+    }
+
+    pub fn from_units(units: &[SubtitleUnit]) -> Self {
+        //! Counts the kanji in the dialogue of `units`, leaving out speaker
+        //! labels and sound effects, as
+        //! [`EpisodeBreakdown::from_units`](crate::dataprep::processing::EpisodeBreakdown::from_units)
+        //! does.
+
+        let dialogue: Vec<String> = units
+            .iter()
+            .map(|unit| spoken_text(&unit.segments()))
+            .collect();
+        KanjiReport::from_text(&dialogue.join("\n"))
+    }
+
+    /// _Jōyō_ kanji, _kyōiku_ ones included.
+    pub fn joyo(&self) -> usize {
+        self.kyoiku_by_grade.iter().sum::<usize>() + self.secondary_joyo
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+pub fn kanji_category(c: char) -> Option<KanjiCategory> {
+    //! The list `c` belongs to, or `None` if `c` isn’t a kanji. Marks that
+    //! stand in for kanji, such as `々` and `〆`, aren’t on any list and give
+    //! `None` too, so that they don’t count as _hyōgai_.
+
+    if !is_ideograph(c) {
+        return None;
+    }
+
+    let category = if let Some(&grade) = KYOIKU_GRADES.get(&c) {
+        KanjiCategory::Kyoiku { grade }
+    } else if JOYO.contains(&c) {
+        KanjiCategory::Joyo
+    } else if JINMEIYO.contains(&c) {
+        KanjiCategory::Jinmeiyo
+    } else {
+        KanjiCategory::Hyogai
+    };
+    Some(category)
+}
+
+fn read_list(list: &str) -> HashSet<char> {
+    list.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_bundled_lists() {
+        assert_eq!(JOYO.len(), 2136);
+        assert_eq!(KYOIKU_GRADES.len(), 1026);
+
+        let mut per_grade = [0; 6];
+        for grade in KYOIKU_GRADES.values() {
+            per_grade[*grade as usize - 1] += 1;
+        }
+        assert_eq!(per_grade, [80, 160, 200, 202, 193, 191]);
+
+        assert!(KYOIKU_GRADES.keys().all(|c| JOYO.contains(c)));
+        assert!(JINMEIYO.is_disjoint(&JOYO));
+    }
+
+    #[test]
+    fn test_kanji_category() {
+        assert_eq!(
+            kanji_category('一'),
+            Some(KanjiCategory::Kyoiku { grade: 1 })
+        );
+        assert_eq!(kanji_category('刑'), Some(KanjiCategory::Joyo));
+        assert_eq!(kanji_category('狡'), Some(KanjiCategory::Hyogai));
+        assert_eq!(kanji_category('槙'), Some(KanjiCategory::Jinmeiyo));
+        assert_eq!(kanji_category('々'), None);
+        assert_eq!(kanji_category('𪜀'), Some(KanjiCategory::Hyogai));
+        assert_eq!(kanji_category('あ'), None);
+        assert!(KanjiCategory::Kyoiku { grade: 3 }.is_joyo());
+        assert!(!KanjiCategory::Jinmeiyo.is_joyo());
+
+        let report = KanjiReport::from_text("狡噛 慎也だ。狡い");
+        assert_eq!((report.kanji, report.distinct_kanji), (5, 4));
+        assert_eq!(report.hyogai, 3);
+        assert_eq!(
+            report.hyogai_kanji[0],
+            KanjiCount {
+                kanji: '狡',
+                occurrences: 2
+            }
+        );
+        assert!((report.hyogai_ratio - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_episode_kanji_report() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let report = KanjiReport::from_units(&units);
+
+        assert_eq!(
+            report.joyo() + report.jinmeiyo + report.hyogai,
+            report.kanji
+        );
+        assert!(report.hyogai_ratio > 0.0 && report.hyogai_ratio < 0.1);
+        assert!(report.hyogai_kanji.iter().any(|count| count.kanji == '狡'));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::dataprep::ingestion::{PathError, SafeFilePath};
+use crate::dataprep::normalisation::{LineEnding, NormalisedLines};
+use crate::types::framerate::Framerate;
+use crate::types::srt_index::SrtIndex;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::TimestampError;
+use crate::types::timing::Timing;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Write};
+use std::path::Path;
+
+/// Separates the lines of a subtitle in MicroDVD text.
+const LINE_SEPARATOR: char = '|';
+
+#[derive(Debug)]
+pub enum MicroDvdError {
+    Io(io::Error),
+    Path(PathError),
+    /// A line doesn’t start with `{start}{end}`, a frame number isn’t a
+    /// whole number, or the end frame is earlier than the start frame.
+    MalformedLine {
+        line_number: usize,
+        message: String,
+    },
+    /// A frame is too late to be a [`Timestamp`](crate::types::timestamp::Timestamp)
+    /// at the given framerate.
+    Timestamp {
+        line_number: usize,
+        error: TimestampError,
+    },
+}
+
+impl Display for MicroDvdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MicroDvdError::Io(e) => write!(f, "I/O error: {}", e),
+            MicroDvdError::Path(e) => write!(f, "Path error: {}", e),
+            MicroDvdError::MalformedLine {
+                line_number,
+                message,
+            } => write!(f, "Line {}: {}", line_number, message),
+            MicroDvdError::Timestamp { line_number, error } => {
+                write!(f, "Line {}: invalid frame: {:?}", line_number, error)
+            }
+        }
+    }
+}
+
+impl Error for MicroDvdError {}
+
+impl From<io::Error> for MicroDvdError {
+    fn from(error: io::Error) -> Self {
+        MicroDvdError::Io(error)
+    }
+}
+
+impl From<PathError> for MicroDvdError {
+    fn from(error: PathError) -> Self {
+        MicroDvdError::Path(error)
+    }
+}
+
+pub fn read_microdvd<R: BufRead>(
+    reader: R,
+    fps: Framerate,
+) -> Result<Vec<SubtitleUnit>, MicroDvdError> {
+    //! Reads `{start}{end}text` lines, turning frame numbers into timestamps at
+    //! `fps`. Lines of a subtitle are separated by `|`; formatting codes such as
+    //! `{y:i}` are left in the text.
+    //!
+    //! Many files start with `{1}{1}23.976` to declare their framerate. That
+    //! line isn’t a subtitle, and the rate it declares is used instead of `fps`.
+
+    let mut lines = NormalisedLines::new(reader);
+    let mut units = Vec::new();
+    let mut fps = fps;
+
+    while let Some(line) = lines.read_line()? {
+        let line_number = line.line_number;
+        if line.text.is_empty() {
+            continue;
+        }
+
+        let (start, end, text) =
+            split_frames(&line.text).ok_or_else(|| MicroDvdError::MalformedLine {
+                line_number,
+                message: "Expected {start}{end} followed by text".to_string(),
+            })?;
+
+        if units.is_empty()
+            && start <= 1
+            && end <= 1
+            && let Ok(declared) = text.trim().parse::<Framerate>()
+        {
+            fps = declared;
+            continue;
+        }
+        if start > end {
+            return Err(MicroDvdError::MalformedLine {
+                line_number,
+                message: "Start frame is later than end frame".to_string(),
+            });
+        }
+
+        let timestamp = |frame: u64| {
+            fps.frame_to_timestamp(frame)
+                .map_err(|error| MicroDvdError::Timestamp { line_number, error })
+        };
+        units.push(SubtitleUnit {
+            index: SrtIndex::from(units.len() as u32 + 1),
+            timing: Timing {
+                start: timestamp(start)?,
+                end: timestamp(end)?,
+            },
+            lines: text.split(LINE_SEPARATOR).map(str::to_string).collect(),
+        });
+    }
+
+    Ok(units)
+}
+
+pub fn read_microdvd_str(
+    content: &str,
+    fps: Framerate,
+) -> Result<Vec<SubtitleUnit>, MicroDvdError> {
+    read_microdvd(Cursor::new(content), fps)
+}
+
+pub fn read_microdvd_file(
+    path: impl AsRef<Path>,
+    fps: Framerate,
+) -> Result<Vec<SubtitleUnit>, MicroDvdError> {
+    // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::with_extensions(raw_path, &["sub", "txt"])?;
+
+    read_microdvd(BufReader::new(File::open(checked_path)?), fps)
+}
+
+/// Splits `{start}{end}text` into its frame numbers and text.
+fn split_frames(line: &str) -> Option<(u64, u64, &str)> {
+    let frame = |rest: &str| -> Option<(u64, usize)> {
+        let inner = rest.strip_prefix('{')?;
+        let close = inner.find('}')?;
+        Some((inner[..close].trim().parse().ok()?, close + 2))
+    };
+
+    let (start, start_len) = frame(line)?;
+    let (end, end_len) = frame(&line[start_len..])?;
+    Some((start, end, &line[start_len + end_len..]))
+}
+
+/// Writes [`SubtitleUnit`]s as MicroDVD to any `io::Write`, one line per unit.
+/// Timestamps are rounded to the nearest frame at the writer’s framerate.
+///
+/// ```text
+/// let mut writer = MicroDvdWriter::new(File::create("out.sub")?, Framerate::NTSC_FILM);
+/// writer.write_units(&units)?;
+/// ```
+#[derive(Debug)]
+pub struct MicroDvdWriter<W: Write> {
+    inner: W,
+    fps: Framerate,
+    line_ending: LineEnding,
+}
+
+impl<W: Write> MicroDvdWriter<W> {
+    /// Creates a writer using `\n` line endings.
+    pub fn new(inner: W, fps: Framerate) -> Self {
+        MicroDvdWriter {
+            inner,
+            fps,
+            line_ending: LineEnding::Lf,
+        }
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    pub fn write_unit(&mut self, unit: &SubtitleUnit) -> io::Result<()> {
+        write!(
+            self.inner,
+            "{{{}}}{{{}}}{}{}",
+            self.fps.timestamp_to_frame(unit.timing.start),
+            self.fps.timestamp_to_frame(unit.timing.end),
+            unit.lines.join(&LINE_SEPARATOR.to_string()),
+            self.line_ending.as_str()
+        )
+    }
+
+    pub fn write_units<'a>(
+        &mut self,
+        units: impl IntoIterator<Item = &'a SubtitleUnit>,
+    ) -> io::Result<()> {
+        for unit in units {
+            self.write_unit(unit)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+pub fn to_microdvd_string(units: &[SubtitleUnit], fps: Framerate) -> String {
+    //! Writes `units` as MicroDVD at `fps` with `\n` line endings.
+
+    let mut writer = MicroDvdWriter::new(Vec::new(), fps);
+    writer
+        .write_units(units)
+        .expect("Writing to a Vec can’t fail");
+
+    String::from_utf8(writer.into_inner()).expect("MicroDVD output is always UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_read_microdvd() {
+        let input = "{1}{1}25\n{1025}{1100}{y:i}犯罪係数|測定中\n\n{1200}{1250}シビュラ\n";
+        let units = read_microdvd_str(input, Framerate::NTSC_FILM).unwrap();
+
+        // The declared 25 fps wins over the 23.976 passed in.
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].timing.to_string(), "00:00:41,000 --> 00:00:44,000");
+        assert_eq!(units[0].lines, vec!["{y:i}犯罪係数", "測定中"]);
+        assert_eq!(units[1].index.value(), 2);
+
+        assert!(matches!(
+            read_microdvd_str("{12}text\n", Framerate::PAL),
+            Err(MicroDvdError::MalformedLine { line_number: 1, .. })
+        ));
+        assert!(matches!(
+            read_microdvd_str("{1}{2}Hi\n{100}{50}text\n", Framerate::PAL),
+            Err(MicroDvdError::MalformedLine { line_number: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_frames_and_timestamps() {
+        let fps = Framerate::NTSC_FILM;
+        // 1025 × 1001 ÷ 24000 s = 42.75104… s.
+        let timestamp = fps.frame_to_timestamp(1025).unwrap();
+        assert_eq!(timestamp.to_string(), "00:00:42,751");
+        assert_eq!(fps.timestamp_to_frame(timestamp), 1025);
+    }
+
+    #[test]
+    fn test_srt_episode_round_trips_within_a_frame() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let fps = Framerate::NTSC_FILM;
+        let back = read_microdvd_str(&to_microdvd_string(&units, fps), fps).unwrap();
+
+        assert_eq!(back.len(), units.len());
+        for (original, converted) in units.iter().zip(&back) {
+            assert_eq!(original.lines, converted.lines);
+            let drift = original
+                .timing
+                .start
+                .to_millis()
+                .abs_diff(converted.timing.start.to_millis());
+            // Half a frame at 23.976 fps is about 21 ms.
+            assert!(drift <= 21, "{} vs {}", original.timing, converted.timing);
+        }
+    }
+}
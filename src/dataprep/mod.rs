@@ -1,5 +1,20 @@
+pub mod ass;
+pub mod attribution;
 pub mod cleaning;
+pub mod detection;
+pub mod diagnostics;
+pub mod glossary;
 pub mod ingestion;
+pub mod kanji;
+pub mod microdvd;
+pub mod modelling;
+pub mod normalisation;
 pub mod parser;
+pub mod pipeline;
 pub mod processing;
-pub mod subtitle_set_builder;
+pub mod retiming;
+pub mod subviewer;
+pub mod ttml;
+pub mod validation;
+pub mod webvtt;
+pub mod writer;
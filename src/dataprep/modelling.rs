@@ -0,0 +1,334 @@
+use crate::dataprep::kanji::{KanjiCategory, kanji_category};
+use crate::dataprep::pipeline::{CleaningPipeline, PipelineError};
+use crate::dataprep::processing::{ScriptClass, classify};
+use crate::types::segment::spoken_text;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::Timestamp;
+use serde::{Serialize, Serializer};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+/// Everything known about one character of an episode. Kana, kanji,
+/// punctuation and the rest share this one schema; fields that only make sense
+/// for kanji are `None` or `false` for everything else.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CharacterRecord {
+    pub character: char,
+    pub occurrences: usize,
+    /// How many units the character appears in.
+    pub units: usize,
+    pub script: ScriptClass,
+    /// Serialises as `kyoiku`, `joyo`, `jinmeiyo` or `hyogai`, with the grade
+    /// in [`kyoiku_grade`](CharacterRecord::kyoiku_grade).
+    #[serde(serialize_with = "serialize_category")]
+    pub kanji_category: Option<KanjiCategory>,
+    pub kyoiku_grade: Option<u8>,
+    pub joyo: bool,
+    /// The start of the first unit the character appears in.
+    pub first_seen: Timestamp,
+    /// The start of the last unit the character appears in.
+    pub last_seen: Timestamp,
+}
+
+/// The order of a [`CharacterTable`]. Ties are broken by code point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CharacterOrder {
+    /// Most occurrences first.
+    #[default]
+    Occurrences,
+    /// Appearing in the most units first.
+    Units,
+    /// Earliest first appearance first.
+    FirstSeen,
+    CodePoint,
+}
+
+/// Which records [`CharacterTable::filter`] keeps. Every field left at its
+/// default keeps everything, so set only the ones you need:
+///
+/// ```text
+/// let rare_kanji = table.filter(&CharacterFilter {
+///     scripts: vec![ScriptClass::Kanji],
+///     joyo: Some(false),
+///     ..CharacterFilter::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharacterFilter {
+    /// Keep only these scripts; empty keeps all.
+    pub scripts: Vec<ScriptClass>,
+    /// Keep only kanji in these categories; empty keeps all characters.
+    pub kanji_categories: Vec<KanjiCategory>,
+    pub joyo: Option<bool>,
+    pub min_occurrences: usize,
+    pub min_units: usize,
+}
+
+impl CharacterFilter {
+    pub fn matches(&self, record: &CharacterRecord) -> bool {
+        (self.scripts.is_empty() || self.scripts.contains(&record.script))
+            && (self.kanji_categories.is_empty()
+                || record
+                    .kanji_category
+                    .is_some_and(|category| self.kanji_categories.contains(&category)))
+            && self.joyo.is_none_or(|joyo| record.joyo == joyo)
+            && record.occurrences >= self.min_occurrences
+            && record.units >= self.min_units
+    }
+}
+
+/// One [`CharacterRecord`] per distinct character, most frequent first unless
+/// re-sorted with [`CharacterTable::sort`]. Whitespace isn’t recorded.
+///
+/// Serialises to JSON as a list of records, e.g. via
+/// [`CharacterTable::to_json`], or to CSV via [`CharacterTable::write_csv`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct CharacterTable {
+    records: Vec<CharacterRecord>,
+}
+
+/// Builder for [`CharacterTable`].
+///
+/// ```text
+/// let table = CharacterTable::builder()
+///     .pipeline(CleaningPipeline::standard(ParenthesisPolicy::AutoClose))
+///     .build(&units)?;
+/// ```
+#[derive(Debug, Default)]
+pub struct CharacterTableBuilder {
+    pipeline: Option<CleaningPipeline>,
+}
+
+impl CharacterTableBuilder {
+    /// Counts the output of `pipeline` for each unit instead of the unit’s
+    /// dialogue.
+    pub fn pipeline(mut self, pipeline: CleaningPipeline) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    pub fn build(self, units: &[SubtitleUnit]) -> Result<CharacterTable, PipelineError> {
+        //! Records every character of `units`. Without a pipeline, only the
+        //! dialogue is counted, as in
+        //! [`EpisodeBreakdown::from_units`](crate::dataprep::processing::EpisodeBreakdown::from_units).
+
+        let mut records: Vec<CharacterRecord> = Vec::new();
+        let mut positions: HashMap<char, usize> = HashMap::new();
+
+        for unit in units {
+            let text = match &self.pipeline {
+                Some(pipeline) => pipeline.clean_unit(unit)?,
+                None => spoken_text(&unit.segments()),
+            };
+            let start = unit.timing.start;
+            let mut in_unit: HashSet<char> = HashSet::new();
+
+            for c in text.chars().filter(|c| !c.is_whitespace()) {
+                let position = *positions.entry(c).or_insert_with(|| {
+                    records.push(new_record(c, start));
+                    records.len() - 1
+                });
+                let record = &mut records[position];
+                record.occurrences += 1;
+                record.last_seen = start;
+                if in_unit.insert(c) {
+                    record.units += 1;
+                }
+            }
+        }
+
+        let mut table = CharacterTable { records };
+        table.sort(CharacterOrder::default());
+        Ok(table)
+    }
+}
+
+impl CharacterTable {
+    pub fn builder() -> CharacterTableBuilder {
+        CharacterTableBuilder::default()
+    }
+
+    pub fn records(&self) -> &[CharacterRecord] {
+        &self.records
+    }
+
+    pub fn get(&self, c: char) -> Option<&CharacterRecord> {
+        self.records.iter().find(|record| record.character == c)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn sort(&mut self, order: CharacterOrder) {
+        match order {
+            CharacterOrder::Occurrences => self
+                .records
+                .sort_by_key(|record| (Reverse(record.occurrences), record.character)),
+            CharacterOrder::Units => self
+                .records
+                .sort_by_key(|record| (Reverse(record.units), record.character)),
+            CharacterOrder::FirstSeen => self
+                .records
+                .sort_by_key(|record| (record.first_seen, record.character)),
+            CharacterOrder::CodePoint => self.records.sort_by_key(|record| record.character),
+        }
+    }
+
+    /// A new table of the records matching `filter`, in the same order.
+    pub fn filter(&self, filter: &CharacterFilter) -> CharacterTable {
+        CharacterTable {
+            records: self
+                .records
+                .iter()
+                .filter(|record| filter.matches(record))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Writes a header row and one row per record.
+    pub fn write_csv<W: Write>(&self, destination: W) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(destination);
+        for record in &self.records {
+            writer.serialize(record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn new_record(c: char, first_seen: Timestamp) -> CharacterRecord {
+    let category = kanji_category(c);
+    CharacterRecord {
+        character: c,
+        occurrences: 0,
+        units: 0,
+        script: classify(c),
+        kanji_category: category,
+        kyoiku_grade: match category {
+            Some(KanjiCategory::Kyoiku { grade }) => Some(grade),
+            _ => None,
+        },
+        joyo: category.is_some_and(KanjiCategory::is_joyo),
+        first_seen,
+        last_seen: first_seen,
+    }
+}
+
+fn serialize_category<S: Serializer>(
+    category: &Option<KanjiCategory>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let label = category.map(|category| match category {
+        KanjiCategory::Kyoiku { .. } => "kyoiku",
+        KanjiCategory::Joyo => "joyo",
+        KanjiCategory::Jinmeiyo => "jinmeiyo",
+        KanjiCategory::Hyogai => "hyogai",
+    });
+    label.serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::cleaning::ParenthesisPolicy;
+    use crate::dataprep::parser::SubtitleParser;
+
+    const INPUT: &str = "1\n00:00:01,000 --> 00:00:02,000\n（狡噛）刑事だ\n\n\
+        2\n00:00:03,000 --> 00:00:04,000\n刑事か 刑事\n\n\
+        3\n00:00:05,000 --> 00:00:06,000\n狡噛だ\n";
+
+    #[test]
+    fn test_build_sort_and_filter() {
+        let units = SubtitleParser::new().read_str(INPUT).unwrap();
+        let mut table = CharacterTable::builder().build(&units).unwrap();
+
+        let order: String = table
+            .records()
+            .iter()
+            .map(|record| record.character)
+            .collect();
+        assert_eq!(order, "事刑だか噛狡");
+
+        let record = table.get('刑').unwrap();
+        assert_eq!((record.occurrences, record.units), (3, 2));
+        assert_eq!(record.first_seen.to_string(), "00:00:01,000");
+        assert_eq!(record.last_seen.to_string(), "00:00:03,000");
+        assert_eq!(record.kanji_category, Some(KanjiCategory::Joyo));
+        assert!(record.joyo);
+        assert_eq!(table.get('事').unwrap().kyoiku_grade, Some(3));
+
+        // Speaker labels aren’t dialogue.
+        assert_eq!(
+            table.get('狡').unwrap().first_seen.to_string(),
+            "00:00:05,000"
+        );
+
+        let hyogai = table.filter(&CharacterFilter {
+            joyo: Some(false),
+            scripts: vec![ScriptClass::Kanji],
+            ..CharacterFilter::default()
+        });
+        let hyogai: String = hyogai
+            .records()
+            .iter()
+            .map(|record| record.character)
+            .collect();
+        assert_eq!(hyogai, "噛狡");
+
+        table.sort(CharacterOrder::FirstSeen);
+        assert_eq!(table.records()[0].character, 'だ');
+        assert_eq!(table.records().last().unwrap().character, '狡');
+
+        let mut csv = Vec::new();
+        table
+            .filter(&CharacterFilter {
+                min_occurrences: 3,
+                ..CharacterFilter::default()
+            })
+            .write_csv(&mut csv)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "character,occurrences,units,script,kanji_category,kyoiku_grade,joyo,first_seen,last_seen\n\
+            事,3,2,kanji,kyoiku,3,true,\"00:00:01,000\",\"00:00:03,000\"\n\
+            刑,3,2,kanji,joyo,,true,\"00:00:01,000\",\"00:00:03,000\"\n"
+        );
+    }
+
+    #[test]
+    fn test_episode_table_with_pipeline() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let table = CharacterTable::builder()
+            .pipeline(CleaningPipeline::standard(ParenthesisPolicy::AutoClose))
+            .build(&units)
+            .unwrap();
+
+        assert!(table.len() > 100);
+        assert!(
+            table
+                .records()
+                .iter()
+                .all(|record| record.units <= record.occurrences)
+        );
+        // The standard pipeline strips parentheses and the unwanted characters.
+        assert!(table.get('（').is_none());
+        assert!(table.get('♪').is_none());
+
+        let json = table.to_json().unwrap();
+        assert!(json.starts_with('['));
+    }
+}
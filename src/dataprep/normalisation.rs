@@ -0,0 +1,246 @@
+use serde::Serialize;
+use std::io::{self, BufRead, ErrorKind};
+
+const BYTE_ORDER_MARK: &str = "\u{FEFF}";
+
+/// A change made to the raw text before it reaches the type parsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalisation {
+    /// A UTF-8 byte-order mark was removed from the start of the source.
+    ByteOrderMark,
+    /// `\r\n` line endings were treated as `\n`.
+    CrlfLineEndings,
+    /// Lone `\r` line endings (classic Mac OS) were treated as `\n`.
+    CrLineEndings,
+    /// Spaces or tabs at the end of a line were removed.
+    TrailingWhitespace,
+}
+
+/// Tally of what [`NormalisedLines`] has changed so far. Line counts are per
+/// line ending, so a file with more than one non-zero count has mixed endings.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NormalisationReport {
+    pub byte_order_mark: bool,
+    pub lf_lines: usize,
+    pub crlf_lines: usize,
+    pub cr_lines: usize,
+    pub trailing_whitespace_lines: usize,
+}
+
+impl NormalisationReport {
+    /// The normalisations that changed at least one line, in a fixed order.
+    pub fn applied(&self) -> Vec<Normalisation> {
+        let mut applied = Vec::new();
+        if self.byte_order_mark {
+            applied.push(Normalisation::ByteOrderMark);
+        }
+        if self.crlf_lines > 0 {
+            applied.push(Normalisation::CrlfLineEndings);
+        }
+        if self.cr_lines > 0 {
+            applied.push(Normalisation::CrLineEndings);
+        }
+        if self.trailing_whitespace_lines > 0 {
+            applied.push(Normalisation::TrailingWhitespace);
+        }
+        applied
+    }
+
+    /// The most common line ending so far; `\n` if there were no line endings.
+    pub fn dominant_line_ending(&self) -> LineEnding {
+        [
+            (self.crlf_lines, LineEnding::CrLf),
+            (self.cr_lines, LineEnding::Cr),
+            (self.lf_lines, LineEnding::Lf),
+        ]
+        .into_iter()
+        .max_by_key(|&(count, _)| count)
+        .map_or(LineEnding::Lf, |(_, ending)| ending)
+    }
+
+    pub fn has_mixed_line_endings(&self) -> bool {
+        [self.lf_lines, self.crlf_lines, self.cr_lines]
+            .iter()
+            .filter(|&&count| count > 0)
+            .count()
+            > 1
+    }
+}
+
+/// One line of the source with its line ending and any normalised characters
+/// removed. `line_number` is 1-based; `byte_offset` is where `text` starts in
+/// the raw source, so it skips over a removed byte-order mark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalisedLine {
+    pub text: String,
+    pub line_number: usize,
+    pub byte_offset: usize,
+}
+
+/// Reads lines from a `BufRead` source, accepting `\n`, `\r\n` and lone `\r`
+/// as line endings (mixed freely), dropping a leading byte-order mark and
+/// trailing spaces and tabs. What was changed is recorded in a
+/// [`NormalisationReport`].
+#[derive(Debug)]
+pub struct NormalisedLines<R: BufRead> {
+    reader: R,
+    line_number: usize,
+    byte_offset: usize,
+    report: NormalisationReport,
+}
+
+impl<R: BufRead> NormalisedLines<R> {
+    pub fn new(reader: R) -> Self {
+        NormalisedLines {
+            reader,
+            line_number: 0,
+            byte_offset: 0,
+            report: NormalisationReport::default(),
+        }
+    }
+
+    pub fn report(&self) -> &NormalisationReport {
+        &self.report
+    }
+
+    /// Returns the next line, or `None` at the end of the source. Invalid UTF-8
+    /// is an [`ErrorKind::InvalidData`] error, as with `BufRead::lines`.
+    pub fn read_line(&mut self) -> io::Result<Option<NormalisedLine>> {
+        let mut raw: Vec<u8> = Vec::new();
+        let mut bytes_read = 0;
+        let mut ending: Option<LineEnding> = None;
+
+        // Read up to and including the first `\n` or `\r`.
+        while ending.is_none() {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+            let consumed = match available.iter().position(|&b| b == b'\n' || b == b'\r') {
+                Some(i) => {
+                    raw.extend_from_slice(&available[..i]);
+                    ending = Some(if available[i] == b'\n' {
+                        LineEnding::Lf
+                    } else {
+                        LineEnding::Cr
+                    });
+                    i + 1
+                }
+                None => {
+                    raw.extend_from_slice(available);
+                    available.len()
+                }
+            };
+            self.reader.consume(consumed);
+            bytes_read += consumed;
+        }
+
+        // A `\r` directly followed by `\n` is one CRLF ending, even when the two
+        // land in different buffer fills.
+        if ending == Some(LineEnding::Cr) && self.reader.fill_buf()?.first() == Some(&b'\n') {
+            self.reader.consume(1);
+            bytes_read += 1;
+            ending = Some(LineEnding::CrLf);
+        }
+
+        if bytes_read == 0 {
+            return Ok(None);
+        }
+
+        match ending {
+            Some(LineEnding::Lf) => self.report.lf_lines += 1,
+            Some(LineEnding::CrLf) => self.report.crlf_lines += 1,
+            Some(LineEnding::Cr) => self.report.cr_lines += 1,
+            None => {}
+        }
+
+        let mut text =
+            String::from_utf8(raw).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+        let mut byte_offset = self.byte_offset;
+        self.byte_offset += bytes_read;
+        self.line_number += 1;
+
+        if self.line_number == 1 && text.starts_with(BYTE_ORDER_MARK) {
+            text.drain(..BYTE_ORDER_MARK.len());
+            byte_offset += BYTE_ORDER_MARK.len();
+            self.report.byte_order_mark = true;
+        }
+
+        let trimmed_len = text.trim_end_matches([' ', '\t']).len();
+        if trimmed_len < text.len() {
+            text.truncate(trimmed_len);
+            self.report.trailing_whitespace_lines += 1;
+        }
+
+        Ok(Some(NormalisedLine {
+            text,
+            line_number: self.line_number,
+            byte_offset,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    fn read_all<R: BufRead>(lines: &mut NormalisedLines<R>) -> Vec<String> {
+        let mut texts = Vec::new();
+        while let Some(line) = lines.read_line().unwrap() {
+            texts.push(line.text);
+        }
+        texts
+    }
+
+    #[test]
+    fn test_mixed_line_endings_and_bom() {
+        let input = "\u{FEFF}1\r\n00:00:01,000 --> 00:00:02,000 \rHello\t\n\nEnd";
+        let mut lines = NormalisedLines::new(Cursor::new(input));
+
+        assert_eq!(
+            read_all(&mut lines),
+            vec!["1", "00:00:01,000 --> 00:00:02,000", "Hello", "", "End"]
+        );
+        assert_eq!(
+            lines.report().applied(),
+            vec![
+                Normalisation::ByteOrderMark,
+                Normalisation::CrlfLineEndings,
+                Normalisation::CrLineEndings,
+                Normalisation::TrailingWhitespace,
+            ]
+        );
+        assert!(lines.report().has_mixed_line_endings());
+    }
+
+    #[test]
+    fn test_crlf_split_across_buffer_fills() {
+        // A one-byte buffer forces `\r` and `\n` into separate fills.
+        let reader = BufReader::with_capacity(1, Cursor::new("a\r\nb\r\n"));
+        let mut lines = NormalisedLines::new(reader);
+
+        assert_eq!(read_all(&mut lines), vec!["a", "b"]);
+        assert_eq!(lines.report().crlf_lines, 2);
+        assert_eq!(lines.report().cr_lines, 0);
+    }
+}
@@ -1,11 +1,21 @@
-use crate::dataprep::subtitle_set_builder::SubtitleUnit;
-use crate::types::srt_index::SrtIndexError;
-use crate::types::timing::TimingError;
+use crate::dataprep::diagnostics::{ErrorContext, SourceLocation};
+use crate::dataprep::ingestion::{
+    DetectedEncoding, ENCODING_SNIFF_WINDOW, EncodingSource, PathError, SafeFilePath,
+    SubtitleEncoding, TranscodingReader, detect_encoding,
+};
+use crate::dataprep::normalisation::{NormalisationReport, NormalisedLine, NormalisedLines};
+use crate::types::srt_index::{SrtIndex, SrtIndexError};
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::Timestamp;
+use crate::types::timing::{Timing, TimingError};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
-use std::io::BufRead;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor};
 use std::path::Path;
 
+const TIMING_SEPARATOR: &str = "-->";
+
 /// Result type for parsing individual subtitle units. Each unit's parse can succeed with
 /// a SubtitleUnit or fail with a SubtitleParserError.
 pub type UnitResult = Result<SubtitleUnit, SubtitleParserError>;
@@ -14,23 +24,51 @@ pub type UnitResult = Result<SubtitleUnit, SubtitleParserError>;
 /// whether the parser setup succeeded, while each item in the iterator is a UnitResult.
 pub type ParseResult<I> = Result<I, SubtitleParserError>;
 
+/// Errors about the content of a file carry an [`ErrorContext`] saying where in
+/// the source they happened; pass them to
+/// [`render_diagnostic`](crate::dataprep::diagnostics::render_diagnostic) to show
+/// the offending lines.
 #[derive(Debug)]
 pub enum SubtitleParserError {
     Io(std::io::Error),
-    MalformedUnit(String),
-    Index(SrtIndexError),
-    Timing(TimingError),
+    Path(PathError),
+    MalformedUnit(String, Box<ErrorContext>),
+    Index(SrtIndexError, Box<ErrorContext>),
+    Timing(TimingError, Box<ErrorContext>),
+}
+
+impl SubtitleParserError {
+    /// Location and source snippet of the error, if it concerns the file’s content.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            SubtitleParserError::Io(_) | SubtitleParserError::Path(_) => None,
+            SubtitleParserError::MalformedUnit(_, context)
+            | SubtitleParserError::Index(_, context)
+            | SubtitleParserError::Timing(_, context) => Some(context),
+        }
+    }
 }
 
 impl Display for SubtitleParserError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             SubtitleParserError::Io(e) => write!(f, "IO error: {}", e),
-            SubtitleParserError::MalformedUnit(msg) => {
-                write!(f, "Malformed subtitle unit: {}", msg)
-            }
-            SubtitleParserError::Index(e) => write!(f, "Index parsing error: {:?}", e),
-            SubtitleParserError::Timing(e) => write!(f, "Timing parsing error: {:?}", e),
+            SubtitleParserError::Path(e) => write!(f, "File path error: {}", e),
+            SubtitleParserError::MalformedUnit(msg, context) => write!(
+                f,
+                "Malformed subtitle unit at line {}, column {}: {}",
+                context.location.line, context.location.column, msg
+            ),
+            SubtitleParserError::Index(e, context) => write!(
+                f,
+                "Index parsing error at line {}, column {}: {:?}",
+                context.location.line, context.location.column, e
+            ),
+            SubtitleParserError::Timing(e, context) => write!(
+                f,
+                "Timing parsing error at line {}, column {}: {:?}",
+                context.location.line, context.location.column, e
+            ),
         }
     }
 }
@@ -43,79 +81,744 @@ impl From<std::io::Error> for SubtitleParserError {
     }
 }
 
-impl From<SrtIndexError> for SubtitleParserError {
-    fn from(error: SrtIndexError) -> Self {
-        SubtitleParserError::Index(error)
+impl From<PathError> for SubtitleParserError {
+    fn from(error: PathError) -> Self {
+        SubtitleParserError::Path(error)
     }
 }
 
-impl From<TimingError> for SubtitleParserError {
-    fn from(error: TimingError) -> Self {
-        SubtitleParserError::Timing(error)
-    }
+/// How the parser reacts to blocks that can’t be turned into a [`SubtitleUnit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Yield the first [`SubtitleParserError`] and stop.
+    #[default]
+    Strict,
+    /// Skip malformed blocks and record them; see [`SubtitleUnits::skipped`].
+    Lenient,
+    /// Fix missing indices, swapped timestamps and blank lines inside a unit,
+    /// recording each fix (see [`SubtitleUnits::repairs`]). Blocks that still
+    /// can’t be parsed are skipped as in [`ParseMode::Lenient`].
+    Repair,
 }
 
 /// The main subtitle parser. This demonstrates how iterators in Rust can provide lazy
 /// evaluation, which maps nicely to Python generators when you eventually expose this
 /// to Python via PyO3.
 ///
-/// Currently stateless, but you could add fields here to track the current line number
-/// for better error messages, or configuration options like strict mode.
-#[derive(Debug)]
+/// Configure it with [`SubtitleParser::builder`]; [`SubtitleParser::new`] gives a
+/// parser in [`ParseMode::Strict`].
+#[derive(Debug, Default)]
 pub struct SubtitleParser {
-    // Empty for now
+    mode: ParseMode,
+    encoding: Option<SubtitleEncoding>,
+}
+
+/// Builder for [`SubtitleParser`].
+///
+/// ```text
+/// let parser = SubtitleParser::builder().mode(ParseMode::Lenient).build();
+/// ```
+#[derive(Debug, Default)]
+pub struct SubtitleParserBuilder {
+    mode: ParseMode,
+    encoding: Option<SubtitleEncoding>,
+}
+
+impl SubtitleParserBuilder {
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Skips encoding detection and decodes every source as `encoding`.
+    pub fn encoding(mut self, encoding: SubtitleEncoding) -> Self {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    pub fn build(self) -> SubtitleParser {
+        SubtitleParser {
+            mode: self.mode,
+            encoding: self.encoding,
+        }
+    }
 }
 
 impl SubtitleParser {
-    /// Creates a new parser instance.
+    /// Creates a new parser instance in strict mode.
     pub fn new() -> Self {
-        SubtitleParser {}
+        SubtitleParser::default()
+    }
+
+    pub fn builder() -> SubtitleParserBuilder {
+        SubtitleParserBuilder::default()
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.mode
     }
 
     /// Parses subtitle content from any BufRead source and returns an iterator over
     /// SubtitleUnits. This is the core parsing method - it's generic over anything that
     /// implements BufRead, making it flexible for files, strings, network streams, etc.
     ///
-    /// The key idea is to iterate through the lines from the BufRead input and build up
-    /// SubtitleUnits as you go. You'll need to maintain some state as you iterate - for
-    /// example, accumulating lines until you hit a blank line (which separates subtitle
-    /// units). Then parse those accumulated lines into a SubtitleUnit using the FromStr
-    /// implementations you already have for SrtIndex and Timing.
+    /// Nothing is read until the iterator is advanced. Each call to `next` pulls lines
+    /// from the source until one block (lines separated by blank lines) is complete, then
+    /// turns that block into a [`SubtitleUnit`] using the `FromStr` implementations of
+    /// [`SrtIndex`] and [`Timing`].
+    ///
+    /// What happens to a malformed block depends on the parser’s [`ParseMode`]. An IO
+    /// error always yields an `Err` item and ends the iteration.
     ///
-    /// TODO: Consider what should happen with malformed units - skip them or return errors?
-    pub fn parse<'a, T>(&mut self, content: T) -> ParseResult<impl Iterator<Item = UnitResult> + 'a>
+    /// Unless the parser was built with an explicit encoding, whatever `content` has
+    /// buffered up front (see [`ENCODING_SNIFF_WINDOW`]) is used to detect it; the
+    /// source is then transcoded to UTF-8 as it is read.
+    pub fn parse<T>(&mut self, mut content: T) -> ParseResult<SubtitleUnits<T>>
     where
-        T: BufRead + 'a,
+        T: BufRead,
     {
-        // TODO: Implement this! For now returning an empty iterator to make it compile.
-        Ok(std::iter::empty())
+        let encoding = match self.encoding {
+            Some(encoding) => DetectedEncoding {
+                encoding,
+                source: EncodingSource::Override,
+            },
+            None => {
+                let prefix = content.fill_buf()?;
+                detect_encoding(&prefix[..prefix.len().min(ENCODING_SNIFF_WINDOW)])
+            }
+        };
+
+        Ok(SubtitleUnits::new(content, self.mode, encoding))
     }
 
     /// Convenience method to parse from a file path. This handles the SafeFilePath
     /// validation and BufReader setup internally, then calls parse().
     ///
     /// Accepts anything that can be converted to a Path (PathBuf, &Path, &str, String, etc.)
-    /// via the AsRef<Path> trait.
-    ///
-    /// TODO: Implement this by creating a BufReader from the file path and calling parse().
-    /// You'll need to return a different type since the iterator will own the file handle.
+    /// via the AsRef<Path> trait. The returned iterator owns the file handle, so it can
+    /// outlive the path it was created from.
     pub fn parse_file(
         &mut self,
-        _path: impl AsRef<Path>,
-    ) -> ParseResult<impl Iterator<Item = UnitResult>> {
-        // TODO: Implement this!
-        Ok(std::iter::empty())
+        path: impl AsRef<Path>,
+    ) -> ParseResult<SubtitleUnits<BufReader<File>>> {
+        // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+        let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+        let checked_path = SafeFilePath::try_from(raw_path)?;
+        let file = File::open(checked_path)?;
+
+        self.parse(BufReader::with_capacity(ENCODING_SNIFF_WINDOW, file))
     }
 
     /// Convenience method to parse from a string. This wraps the string in a BufReader
     /// (via Cursor) and calls parse().
-    ///
-    /// TODO: Implement this using std::io::Cursor to wrap the string as a BufRead source.
     pub fn parse_str<'a>(
         &mut self,
-        _content: &'a str,
-    ) -> ParseResult<impl Iterator<Item = UnitResult> + 'a> {
-        // TODO: Implement this!
-        Ok(std::iter::empty())
+        content: &'a str,
+    ) -> ParseResult<SubtitleUnits<Cursor<&'a str>>> {
+        self.parse(Cursor::new(content))
+    }
+
+    /// Eagerly parses a whole file into a `Vec<SubtitleUnit>`, failing on the first
+    /// error yielded. Shorthand for collecting the iterator returned by [`Self::parse_file`].
+    pub fn read_file(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<SubtitleUnit>, SubtitleParserError> {
+        self.parse_file(path)?.collect()
+    }
+
+    /// Eagerly parses a string into a `Vec<SubtitleUnit>`, failing on the first
+    /// error yielded. Shorthand for collecting the iterator returned by [`Self::parse_str`].
+    pub fn read_str(&mut self, content: &str) -> Result<Vec<SubtitleUnit>, SubtitleParserError> {
+        self.parse_str(content)?.collect()
+    }
+}
+
+/// Where [`SubtitleUnits`] is within the current block of lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockState {
+    /// Skipping blank lines between (or before) blocks.
+    BetweenBlocks,
+    /// At least one non-blank line has been collected for the current block.
+    InBlock,
+}
+
+/// A block of non-blank lines, together with the line number (1-based) of its
+/// first line and the byte offset at which each line starts.
+#[derive(Debug)]
+struct Block {
+    first_line: usize,
+    lines: Vec<String>,
+    byte_offsets: Vec<usize>,
+}
+
+impl Block {
+    /// Builds the context for an error spanning `span` characters from `column`
+    /// (1-based) on the block’s `line_in_block`-th line (0-based).
+    fn context(
+        &self,
+        line_in_block: usize,
+        column: usize,
+        span: usize,
+        unit_index: Option<u32>,
+    ) -> Box<ErrorContext> {
+        let line = &self.lines[line_in_block];
+        let bytes_before_column: usize = line
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(char::len_utf8)
+            .sum();
+
+        Box::new(ErrorContext {
+            location: SourceLocation {
+                byte_offset: self.byte_offsets[line_in_block] + bytes_before_column,
+                line: self.first_line + line_in_block,
+                column,
+            },
+            span,
+            unit_index,
+            snippet_first_line: self.first_line,
+            snippet: self.lines.clone(),
+        })
+    }
+
+    /// Context covering the whole (trimmed) content of one line.
+    fn line_context(&self, line_in_block: usize, unit_index: Option<u32>) -> Box<ErrorContext> {
+        let (column, span) = trimmed_span(&self.lines[line_in_block]);
+        self.context(line_in_block, column, span, unit_index)
+    }
+
+    fn index_error(&self, line_in_block: usize, error: SrtIndexError) -> SubtitleParserError {
+        SubtitleParserError::Index(error, self.line_context(line_in_block, None))
+    }
+
+    /// Points the error at the timestamp that failed to parse, if only one of them
+    /// did, and at the whole timing line otherwise.
+    fn timing_error(
+        &self,
+        line_in_block: usize,
+        unit_index: Option<u32>,
+        error: TimingError,
+    ) -> SubtitleParserError {
+        let line = &self.lines[line_in_block];
+        let context = match (&error, line.split_once(TIMING_SEPARATOR)) {
+            (TimingError::Timestamp(_), Some((raw_start, raw_end))) => {
+                let (column, span) = if raw_start.trim().parse::<Timestamp>().is_ok() {
+                    let (end_column, end_span) = trimmed_span(raw_end);
+                    let end_offset = raw_start.chars().count() + TIMING_SEPARATOR.len();
+                    (end_offset + end_column, end_span)
+                } else {
+                    trimmed_span(raw_start)
+                };
+                self.context(line_in_block, column, span, unit_index)
+            }
+            _ => self.line_context(line_in_block, unit_index),
+        };
+
+        SubtitleParserError::Timing(error, context)
+    }
+
+    fn missing_timing_error(&self, unit_index: Option<u32>) -> SubtitleParserError {
+        let message = match unit_index {
+            Some(index) => format!("Block with index {} has no timing line", index),
+            None => "Block has no timing line".to_string(),
+        };
+        SubtitleParserError::MalformedUnit(message, self.line_context(0, unit_index))
+    }
+}
+
+/// 1-based column and character length of `line` with surrounding whitespace
+/// ignored. An all-whitespace line gets a span of zero at column 1.
+fn trimmed_span(line: &str) -> (usize, usize) {
+    let leading = line.chars().take_while(|c| c.is_whitespace()).count();
+    (leading + 1, line.trim().chars().count())
+}
+
+/// A block that was dropped in [`ParseMode::Lenient`] or [`ParseMode::Repair`].
+#[derive(Debug)]
+pub struct SkippedBlock {
+    /// Line number (1-based) of the block’s first line.
+    pub line_number: usize,
+    pub error: SubtitleParserError,
+}
+
+/// A fix applied in [`ParseMode::Repair`]. Line numbers are 1-based and point
+/// at the first line of the affected block, except where noted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// The block started with its timing line; `assigned` follows on from the
+    /// previous unit’s index.
+    MissingIndex { line_number: usize, assigned: u32 },
+    /// The start timestamp was later than the end timestamp. `line_number` is
+    /// that of the timing line itself, and `unit_index` the unit’s index (as
+    /// assigned, if the index was missing too).
+    SwappedTimestamps { line_number: usize, unit_index: u32 },
+    /// A blank line split a unit in two; the orphaned text lines were merged
+    /// back into the unit with index `merged_into`.
+    ExtraBlankLines {
+        line_number: usize,
+        merged_into: u32,
+    },
+}
+
+/// Lazy iterator over the subtitle units of an SRT source, returned by
+/// [`SubtitleParser::parse`].
+///
+/// Only one block of lines is held in memory at a time, so an episode can be
+/// streamed unit by unit without loading the whole file. (In repair mode, one
+/// finished unit is also held back, in case the next block turns out to be
+/// text that belongs to it.)
+#[derive(Debug)]
+pub struct SubtitleUnits<T: BufRead> {
+    lines: NormalisedLines<TranscodingReader<T>>,
+    encoding: DetectedEncoding,
+    finished: bool,
+    mode: ParseMode,
+    previous_index: Option<u32>,
+    /// Unit held back in repair mode, with the line number its block starts on.
+    pending: Option<(SubtitleUnit, usize)>,
+    last_unit_line: Option<usize>,
+    deferred_error: Option<SubtitleParserError>,
+    skipped: Vec<SkippedBlock>,
+    repairs: Vec<Repair>,
+}
+
+impl<T: BufRead> SubtitleUnits<T> {
+    fn new(content: T, mode: ParseMode, encoding: DetectedEncoding) -> Self {
+        SubtitleUnits {
+            lines: NormalisedLines::new(TranscodingReader::new(content, encoding.encoding)),
+            encoding,
+            finished: false,
+            mode,
+            previous_index: None,
+            pending: None,
+            last_unit_line: None,
+            deferred_error: None,
+            skipped: Vec::new(),
+            repairs: Vec::new(),
+        }
+    }
+
+    /// Blocks dropped so far. Always empty in [`ParseMode::Strict`].
+    pub fn skipped(&self) -> &[SkippedBlock] {
+        &self.skipped
+    }
+
+    /// Fixes applied so far. Always empty outside [`ParseMode::Repair`].
+    pub fn repairs(&self) -> &[Repair] {
+        &self.repairs
+    }
+
+    /// Encoding the source is decoded from. For anything but UTF-8, byte offsets in
+    /// error contexts refer to the transcoded (UTF-8) text.
+    pub fn encoding(&self) -> DetectedEncoding {
+        self.encoding
+    }
+
+    /// What was changed in the raw text (byte-order mark, line endings, trailing
+    /// whitespace) before it was parsed, for the lines read so far.
+    pub fn normalisations(&self) -> &NormalisationReport {
+        self.lines.report()
+    }
+
+    /// Line number (1-based) on which the most recently yielded unit starts.
+    pub fn last_unit_line(&self) -> Option<usize> {
+        self.last_unit_line
+    }
+
+    /// Reads lines until a complete block has been collected. Returns `None` once
+    /// the source is exhausted and no lines are left over.
+    ///
+    /// A block ends at the first blank line after its content, or at the end of
+    /// the source; the latter covers files without a final newline. Any number of
+    /// blank lines between or after blocks is skipped.
+    fn next_block(&mut self) -> Option<Result<Block, std::io::Error>> {
+        let mut state = BlockState::BetweenBlocks;
+        let mut block = Block {
+            first_line: 0,
+            lines: Vec::new(),
+            byte_offsets: Vec::new(),
+        };
+
+        loop {
+            // Line endings, a byte-order mark and trailing whitespace have already
+            // been dealt with by `NormalisedLines`.
+            let NormalisedLine {
+                text: line,
+                line_number,
+                byte_offset: line_start,
+            } = match self.lines.read_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => return Some(Err(e)),
+            };
+
+            let is_blank = line.trim().is_empty();
+            match (state, is_blank) {
+                (BlockState::BetweenBlocks, true) => continue,
+                (BlockState::BetweenBlocks, false) => {
+                    state = BlockState::InBlock;
+                    block.first_line = line_number;
+                    block.lines.push(line);
+                    block.byte_offsets.push(line_start);
+                }
+                (BlockState::InBlock, false) => {
+                    block.lines.push(line);
+                    block.byte_offsets.push(line_start);
+                }
+                (BlockState::InBlock, true) => break,
+            }
+        }
+
+        match state {
+            BlockState::BetweenBlocks => None,
+            BlockState::InBlock => Some(Ok(block)),
+        }
+    }
+
+    /// Builds a unit from `block`, fixing what [`ParseMode::Repair`] knows how to
+    /// fix. Orphaned text blocks are handled by the caller.
+    fn repair_unit(&mut self, block: Block) -> UnitResult {
+        // `next_block` never returns an empty block, so the first line exists.
+        let (index, timing_line) = match block.lines[0].trim().parse::<SrtIndex>() {
+            Ok(index) => (index, 1),
+            Err(e) => {
+                if Timing::parse_swapping_reversed(block.lines[0].trim()).is_err() {
+                    return Err(block.index_error(0, e));
+                }
+                let assigned = self.previous_index.map_or(1, |previous| previous + 1);
+                self.repairs.push(Repair::MissingIndex {
+                    line_number: block.first_line,
+                    assigned,
+                });
+                (SrtIndex::from(assigned), 0)
+            }
+        };
+        let unit_index = Some(index.value());
+
+        let raw_timing = block
+            .lines
+            .get(timing_line)
+            .ok_or_else(|| block.missing_timing_error(unit_index))?;
+        let (timing, swapped) = Timing::parse_swapping_reversed(raw_timing.trim())
+            .map_err(|e| block.timing_error(timing_line, unit_index, e))?;
+        if swapped {
+            self.repairs.push(Repair::SwappedTimestamps {
+                line_number: block.first_line + timing_line,
+                unit_index: index.value(),
+            });
+        }
+
+        Ok(SubtitleUnit {
+            index,
+            timing,
+            lines: block.lines.into_iter().skip(timing_line + 1).collect(),
+        })
+    }
+
+    /// Handles one block in repair mode. Returns a unit once the one before it is
+    /// known to be complete.
+    fn process_repair_block(&mut self, block: Block) -> Option<(SubtitleUnit, usize)> {
+        if let Some((pending, _)) = self.pending.as_mut().filter(|_| is_orphaned_text(&block)) {
+            self.repairs.push(Repair::ExtraBlankLines {
+                line_number: block.first_line,
+                merged_into: pending.index.value(),
+            });
+            pending.lines.extend(block.lines);
+            return None;
+        }
+
+        let first_line = block.first_line;
+        match self.repair_unit(block) {
+            Ok(unit) => {
+                self.previous_index = Some(unit.index.value());
+                self.pending.replace((unit, first_line))
+            }
+            Err(error) => {
+                self.skipped.push(SkippedBlock {
+                    line_number: first_line,
+                    error,
+                });
+                None
+            }
+        }
+    }
+}
+
+impl<T: BufRead> Iterator for SubtitleUnits<T> {
+    type Item = UnitResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let block = match self.next_block() {
+                Some(Ok(block)) => block,
+                Some(Err(e)) => {
+                    self.finished = true;
+                    self.deferred_error = Some(SubtitleParserError::Io(e));
+                    break;
+                }
+                None => {
+                    self.finished = true;
+                    break;
+                }
+            };
+
+            let first_line = block.first_line;
+            match self.mode {
+                ParseMode::Strict => {
+                    let result = build_unit(block);
+                    match result {
+                        Ok(_) => self.last_unit_line = Some(first_line),
+                        Err(_) => self.finished = true,
+                    }
+                    return Some(result);
+                }
+                ParseMode::Lenient => match build_unit(block) {
+                    Ok(unit) => {
+                        self.last_unit_line = Some(first_line);
+                        return Some(Ok(unit));
+                    }
+                    Err(error) => self.skipped.push(SkippedBlock {
+                        line_number: first_line,
+                        error,
+                    }),
+                },
+                ParseMode::Repair => {
+                    if let Some((unit, unit_line)) = self.process_repair_block(block) {
+                        self.last_unit_line = Some(unit_line);
+                        return Some(Ok(unit));
+                    }
+                }
+            }
+        }
+
+        if let Some((unit, unit_line)) = self.pending.take() {
+            self.last_unit_line = Some(unit_line);
+            return Some(Ok(unit));
+        }
+        self.deferred_error.take().map(Err)
+    }
+}
+
+/// Turns one block of non-blank lines into a [`SubtitleUnit`]. The first line
+/// must be the index, the second the timing, and everything after that is
+/// subtitle content.
+fn build_unit(block: Block) -> UnitResult {
+    // `next_block` never returns an empty block, so the first line exists.
+    let index = block.lines[0]
+        .trim()
+        .parse::<SrtIndex>()
+        .map_err(|e| block.index_error(0, e))?;
+    let unit_index = Some(index.value());
+
+    let raw_timing = block
+        .lines
+        .get(1)
+        .ok_or_else(|| block.missing_timing_error(unit_index))?;
+    let timing = raw_timing
+        .trim()
+        .parse::<Timing>()
+        .map_err(|e| block.timing_error(1, unit_index, e))?;
+
+    Ok(SubtitleUnit {
+        index,
+        timing,
+        lines: block.lines.into_iter().skip(2).collect(),
+    })
+}
+
+/// A block is orphaned text, i.e. the tail of a unit that was split by a stray
+/// blank line, if its first line is neither a bare index nor timing-like. A block
+/// that starts like a unit but is malformed is skipped rather than merged.
+fn is_orphaned_text(block: &Block) -> bool {
+    block.lines.first().is_some_and(|first| {
+        let first = first.trim();
+        first.parse::<SrtIndex>().is_err() && !first.contains(TIMING_SEPARATOR)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPISODE_PATH: &str = "data/raw/psycho-pass-s01e01-jp.srt";
+
+    #[test]
+    fn test_parse_full_episode() {
+        let reader = BufReader::new(File::open(EPISODE_PATH).unwrap());
+        let units: Vec<SubtitleUnit> = SubtitleParser::new()
+            .parse(reader)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(units.len(), 305);
+        assert_eq!(units[0].index, "1".parse::<SrtIndex>().unwrap());
+        assert_eq!(units[0].lines, vec!["♪～".to_string()]);
+        assert_eq!(units[304].index, "305".parse::<SrtIndex>().unwrap());
+    }
+
+    #[test]
+    fn test_parse_reports_normalisations() {
+        let mut units = SubtitleParser::new().parse_file(EPISODE_PATH).unwrap();
+        assert_eq!(units.by_ref().count(), 305);
+
+        let report = units.normalisations();
+        assert!(report.byte_order_mark);
+        assert_eq!(report.crlf_lines, 1348);
+        assert!(!report.has_mixed_line_endings());
+    }
+
+    #[test]
+    fn test_parse_detects_legacy_encodings() {
+        let utf8 = "1\r\n00:00:01,000 --> 00:00:02,000\r\n（狡噛(こうがみ)）フゥ～…\r\n";
+
+        for (encoding, expected) in [
+            (encoding_rs::SHIFT_JIS, SubtitleEncoding::ShiftJis),
+            (encoding_rs::EUC_JP, SubtitleEncoding::EucJp),
+        ] {
+            let (bytes, _, _) = encoding.encode(utf8);
+            let mut units = SubtitleParser::new().parse(Cursor::new(bytes)).unwrap();
+            let unit = units.next().unwrap().unwrap();
+
+            assert_eq!(units.encoding().encoding, expected);
+            assert_eq!(unit.lines, vec!["（狡噛(こうがみ)）フゥ～…".to_string()]);
+        }
+    }
+
+    #[test]
+    fn test_parse_utf16_with_bom_and_override() {
+        let mut bytes: Vec<u8> = vec![0xFF, 0xFE];
+        for unit in "1\n00:00:01,000 --> 00:00:02,000\n足音\n".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let mut units = SubtitleParser::new().parse(Cursor::new(&bytes)).unwrap();
+        assert_eq!(
+            units.next().unwrap().unwrap().lines,
+            vec!["足音".to_string()]
+        );
+        assert_eq!(units.encoding().source, EncodingSource::ByteOrderMark);
+        assert!(units.normalisations().byte_order_mark);
+
+        let mut parser = SubtitleParser::builder()
+            .encoding(SubtitleEncoding::Utf16Le)
+            .build();
+        let units = parser.parse(Cursor::new(&bytes[2..])).unwrap();
+        assert_eq!(units.encoding().source, EncodingSource::Override);
+        assert_eq!(units.count(), 1);
+    }
+
+    #[test]
+    fn test_parse_lone_cr_and_trailing_whitespace() {
+        let input =
+            "1 \r00:00:01,000 --> 00:00:02,000\t\rHello\r\r2\r00:00:03,000 --> 00:00:04,000\rWorld";
+        let units = SubtitleParser::new().read_str(input).unwrap();
+
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].lines, vec!["Hello".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_missing_final_newline_and_extra_blank_lines() {
+        let input = "\n\n1\n00:00:01,000 --> 00:00:02,000\nHello\n\n\n\n2\n00:00:03,000 --> 00:00:04,000\nWorld\nAgain";
+        let units = SubtitleParser::new().read_str(input).unwrap();
+
+        assert_eq!(units.len(), 2);
+        assert_eq!(
+            units[1].lines,
+            vec!["World".to_string(), "Again".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_strict_stops_at_first_malformed_block() {
+        let input = "1\nnot a timing\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nWorld\n";
+        let results: Vec<UnitResult> = SubtitleParser::new().parse_str(input).unwrap().collect();
+
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Err(SubtitleParserError::Timing(_, _))));
+    }
+
+    #[test]
+    fn test_lenient_skips_and_records_malformed_block() {
+        let input = "1\nnot a timing\nHello\n\n2\n00:00:03,000 --> 00:00:04,000\nWorld\n";
+        let mut parser = SubtitleParser::builder().mode(ParseMode::Lenient).build();
+        let mut units = parser.parse_str(input).unwrap();
+        let results: Vec<UnitResult> = units.by_ref().collect();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_ok());
+        assert_eq!(units.skipped().len(), 1);
+        assert_eq!(units.skipped()[0].line_number, 1);
+    }
+
+    #[test]
+    fn test_repair_fixes_index_timing_and_blank_lines() {
+        let input = "1\n00:00:02,000 --> 00:00:01,000\nHello\n\nthere\n\n00:00:03,000 --> 00:00:04,000\nWorld\n";
+        let mut parser = SubtitleParser::builder().mode(ParseMode::Repair).build();
+        let mut units = parser.parse_str(input).unwrap();
+        let results: Vec<SubtitleUnit> = units.by_ref().collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].lines,
+            vec!["Hello".to_string(), "there".to_string()]
+        );
+        assert!(results[0].timing.start < results[0].timing.end);
+        assert_eq!(results[1].index.value(), 2);
+        assert_eq!(
+            units.repairs(),
+            &[
+                Repair::SwappedTimestamps {
+                    line_number: 2,
+                    unit_index: 1
+                },
+                Repair::ExtraBlankLines {
+                    line_number: 5,
+                    merged_into: 1
+                },
+                Repair::MissingIndex {
+                    line_number: 7,
+                    assigned: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repair_skips_malformed_block_after_unit() {
+        let input = "1\n00:00:01,000 --> 00:00:02,000\nA\n\n2\n00:00:03,000 -> 00:00:04,000\nB\n";
+        let mut parser = SubtitleParser::builder().mode(ParseMode::Repair).build();
+        let mut units = parser.parse_str(input).unwrap();
+        let results: Vec<SubtitleUnit> = units.by_ref().collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].lines, vec!["A".to_string()]);
+        assert!(units.repairs().is_empty());
+        assert_eq!(units.skipped().len(), 1);
+        assert_eq!(units.skipped()[0].line_number, 5);
+    }
+
+    #[test]
+    fn test_read_file_matches_parse() {
+        let units = SubtitleParser::new().read_file(EPISODE_PATH).unwrap();
+        assert_eq!(units.len(), 305);
+    }
+
+    #[test]
+    fn test_parse_file_rejects_wrong_extension() {
+        let result = SubtitleParser::new().parse_file("data/raw/episode.txt");
+        assert!(matches!(
+            result,
+            Err(SubtitleParserError::Path(PathError::IncorrectExtension { .. }))
+        ));
+
+        // Other formats have their own readers.
+        let result = SubtitleParser::new().parse_file("data/raw/episode.vtt");
+        let Err(SubtitleParserError::Path(error)) = result else {
+            panic!("expected a path error");
+        };
+        assert_eq!(error.to_string(), "File extension is not .srt");
     }
 }
@@ -0,0 +1,658 @@
+use crate::dataprep::cleaning::{
+    ParenthesisPolicy, RegularKana, SmallKana, UnbalancedParenthesis, convert_mini_kana_to_regular,
+    mini_kana_mappings, read_mini_kana_mappings, read_unwanted_characters,
+    remove_parentheses_and_contents, unwanted_characters,
+};
+use crate::types::segment::{Segment, parse_segments, render_segments};
+use crate::types::subtitle_unit::SubtitleUnit;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
+
+/// The prolonged sound mark.
+const CHOONPU: char = 'ー';
+
+#[derive(Debug)]
+pub enum PipelineError {
+    Io(io::Error),
+    /// A pipeline file that isn’t valid TOML, names an unknown step or gives a
+    /// step an invalid option.
+    Config(toml::de::Error),
+    /// A step was given two options that can’t be used together.
+    ConflictingOptions {
+        step: &'static str,
+        options: [&'static str; 2],
+    },
+    Parenthesis(UnbalancedParenthesis),
+}
+
+impl Display for PipelineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineError::Io(e) => write!(f, "I/O error: {}", e),
+            PipelineError::Config(e) => write!(f, "Invalid pipeline configuration: {}", e),
+            PipelineError::ConflictingOptions {
+                step,
+                options: [first, second],
+            } => write!(
+                f,
+                "Invalid pipeline configuration: {} takes {} or {}, not both",
+                step, first, second
+            ),
+            PipelineError::Parenthesis(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for PipelineError {}
+
+impl From<io::Error> for PipelineError {
+    fn from(error: io::Error) -> Self {
+        PipelineError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for PipelineError {
+    fn from(error: toml::de::Error) -> Self {
+        PipelineError::Config(error)
+    }
+}
+
+impl From<UnbalancedParenthesis> for PipelineError {
+    fn from(error: UnbalancedParenthesis) -> Self {
+        PipelineError::Parenthesis(error)
+    }
+}
+
+/// One transformation of subtitle text. Text passed to a step may hold several
+/// lines separated by `\n`.
+pub trait CleaningStep: Debug {
+    /// The name used for the step in pipeline files, e.g. `strip_parentheses`.
+    fn name(&self) -> &'static str;
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError>;
+}
+
+/// Removes every parenthesis and its contents: speaker labels, furigana and
+/// sound effects alike.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripParentheses {
+    pub unbalanced: ParenthesisPolicy,
+}
+
+impl CleaningStep for StripParentheses {
+    fn name(&self) -> &'static str {
+        "strip_parentheses"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(remove_parentheses_and_contents(text, self.unbalanced)?.text)
+    }
+}
+
+/// Removes sound-effect captions such as `（足音）`, keeping speaker labels and
+/// furigana.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripSoundEffects;
+
+impl CleaningStep for StripSoundEffects {
+    fn name(&self) -> &'static str {
+        "strip_sound_effects"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(without_segments(text, |segment| {
+            matches!(segment, Segment::SoundEffect(_))
+        }))
+    }
+}
+
+/// Removes speaker labels such as `（狡噛）`, keeping sound effects and
+/// furigana.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripSpeakerLabels;
+
+impl CleaningStep for StripSpeakerLabels {
+    fn name(&self) -> &'static str {
+        "strip_speaker_labels"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(without_segments(text, |segment| {
+            matches!(segment, Segment::Speaker(_))
+        }))
+    }
+}
+
+/// Removes every character in a set. Line breaks are always kept.
+#[derive(Debug, Clone, Default)]
+pub struct RemoveCharacters {
+    pub characters: HashSet<char>,
+}
+
+impl RemoveCharacters {
+    /// Removes the characters listed in `data/raw/unwanted_characters.txt`,
+    /// which is compiled into the crate.
+    pub fn unwanted() -> Self {
+        RemoveCharacters {
+            characters: unwanted_characters().clone(),
+        }
+    }
+
+    /// Removes every character in the text file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, PipelineError> {
+        Ok(RemoveCharacters {
+            characters: read_unwanted_characters(path)?,
+        })
+    }
+}
+
+impl CleaningStep for RemoveCharacters {
+    fn name(&self) -> &'static str {
+        "remove_characters"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(text
+            .chars()
+            .filter(|c| *c == '\n' || !self.characters.contains(c))
+            .collect())
+    }
+}
+
+/// What to do with small kana such as `ッ` or `ょ`; see
+/// `documentation/hiragana-katakana-with-small-characters.md`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmallKanaPolicy {
+    Keep,
+    /// Replace each with its regular-sized counterpart, e.g. `ッ` with `ツ`.
+    #[default]
+    Convert,
+    Remove,
+}
+
+#[derive(Debug)]
+pub struct ApplySmallKanaPolicy {
+    pub policy: SmallKanaPolicy,
+    mappings: HashMap<SmallKana, RegularKana>,
+}
+
+impl ApplySmallKanaPolicy {
+    /// Uses the small kana listed in `data/raw/mini_kana_mappings.json`, which
+    /// is compiled into the crate.
+    pub fn new(policy: SmallKanaPolicy) -> Self {
+        ApplySmallKanaPolicy {
+            policy,
+            mappings: mini_kana_mappings().clone(),
+        }
+    }
+
+    /// Uses the small kana in the JSON file at `path` instead, which maps each
+    /// small kana to its regular-sized counterpart.
+    pub fn from_file(
+        policy: SmallKanaPolicy,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, PipelineError> {
+        Ok(ApplySmallKanaPolicy {
+            policy,
+            mappings: read_mini_kana_mappings(path)?,
+        })
+    }
+}
+
+impl CleaningStep for ApplySmallKanaPolicy {
+    fn name(&self) -> &'static str {
+        "small_kana"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(match self.policy {
+            SmallKanaPolicy::Keep => text.to_string(),
+            SmallKanaPolicy::Convert => text
+                .chars()
+                .map(|c| convert_mini_kana_to_regular(&c, &self.mappings))
+                .collect(),
+            SmallKanaPolicy::Remove => text
+                .chars()
+                .filter(|c| !self.mappings.contains_key(&SmallKana(*c)))
+                .collect(),
+        })
+    }
+}
+
+/// Unicode NFKC normalisation: half-width katakana become full-width, and
+/// full-width letters, digits and punctuation (including `（）`) become ASCII.
+/// Run it after any step that looks for full-width parentheses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NormaliseNfkc;
+
+impl CleaningStep for NormaliseNfkc {
+    fn name(&self) -> &'static str {
+        "nfkc"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(text.nfkc().collect())
+    }
+}
+
+/// What to do with _chōonpu_ (`ー`), the mark that lengthens the kana before
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChoonpuPolicy {
+    Keep,
+    #[default]
+    Remove,
+    /// Replace each with the vowel it lengthens, so `コーヒー` becomes
+    /// `コオヒイ`. A mark after anything without a vowel (`ン`, `ッ`, kanji and
+    /// so on) is kept.
+    ExpandVowel,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApplyChoonpuPolicy {
+    pub policy: ChoonpuPolicy,
+}
+
+impl CleaningStep for ApplyChoonpuPolicy {
+    fn name(&self) -> &'static str {
+        "choonpu"
+    }
+
+    fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        Ok(match self.policy {
+            ChoonpuPolicy::Keep => text.to_string(),
+            ChoonpuPolicy::Remove => text.chars().filter(|c| *c != CHOONPU).collect(),
+            ChoonpuPolicy::ExpandVowel => {
+                let mut expanded = String::with_capacity(text.len());
+                let mut previous: Option<char> = None;
+                for c in text.chars() {
+                    let c = match (c, previous.and_then(vowel_of)) {
+                        (CHOONPU, Some(vowel)) => vowel,
+                        _ => c,
+                    };
+                    expanded.push(c);
+                    previous = Some(c);
+                }
+                expanded
+            }
+        })
+    }
+}
+
+/// Cleaning steps run in order. Build one in code:
+///
+/// ```text
+/// let pipeline = CleaningPipeline::new()
+///     .step(StripSoundEffects)
+///     .step(StripSpeakerLabels)
+///     .step(ApplyChoonpuPolicy { policy: ChoonpuPolicy::Keep });
+/// ```
+///
+/// or from a TOML file with one `[[step]]` table per step, in order:
+///
+/// ```toml
+/// [[step]]
+/// name = "strip_parentheses"
+/// unbalanced = "auto_close"     # fail (default), auto_close or leave_untouched
+///
+/// [[step]]
+/// name = "remove_characters"
+/// characters = "…―♪"            # or characters_file = "path/to/list.txt", but
+///                               # not both; defaults to
+///                               # data/raw/unwanted_characters.txt
+///
+/// [[step]]
+/// name = "small_kana"
+/// policy = "remove"             # keep, convert (default) or remove
+/// mappings_file = "kana.json"   # defaults to data/raw/mini_kana_mappings.json
+///
+/// [[step]]
+/// name = "choonpu"
+/// policy = "expand_vowel"       # keep, remove (default) or expand_vowel
+/// ```
+///
+/// The other steps, `strip_sound_effects`, `strip_speaker_labels` and `nfkc`,
+/// take no options.
+#[derive(Debug, Default)]
+pub struct CleaningPipeline {
+    steps: Vec<Box<dyn CleaningStep>>,
+}
+
+/// The TOML form of a [`CleaningPipeline`].
+#[derive(Debug, Deserialize)]
+struct PipelineConfig {
+    #[serde(default, rename = "step")]
+    steps: Vec<StepConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+enum StepConfig {
+    StripParentheses {
+        #[serde(default)]
+        unbalanced: ParenthesisPolicy,
+    },
+    StripSoundEffects,
+    StripSpeakerLabels,
+    RemoveCharacters {
+        characters: Option<String>,
+        characters_file: Option<PathBuf>,
+    },
+    SmallKana {
+        #[serde(default)]
+        policy: SmallKanaPolicy,
+        mappings_file: Option<PathBuf>,
+    },
+    Nfkc,
+    Choonpu {
+        #[serde(default)]
+        policy: ChoonpuPolicy,
+    },
+}
+
+impl CleaningPipeline {
+    /// An empty pipeline, which returns text unchanged.
+    pub fn new() -> Self {
+        CleaningPipeline::default()
+    }
+
+    /// The order [`clean_subtitles`](crate::dataprep::cleaning::clean_subtitles)
+    /// has always used: strip parentheses, remove unwanted characters, then
+    /// convert small kana to regular kana.
+    pub fn standard(unbalanced: ParenthesisPolicy) -> Self {
+        CleaningPipeline::new()
+            .step(StripParentheses { unbalanced })
+            .step(RemoveCharacters::unwanted())
+            .step(ApplySmallKanaPolicy::new(SmallKanaPolicy::Convert))
+    }
+
+    pub fn from_toml_str(config: &str) -> Result<Self, PipelineError> {
+        let config: PipelineConfig = toml::from_str(config)?;
+        let mut pipeline = CleaningPipeline::new();
+
+        for step in config.steps {
+            pipeline.steps.push(match step {
+                StepConfig::StripParentheses { unbalanced } => {
+                    Box::new(StripParentheses { unbalanced })
+                }
+                StepConfig::StripSoundEffects => Box::new(StripSoundEffects),
+                StepConfig::StripSpeakerLabels => Box::new(StripSpeakerLabels),
+                StepConfig::RemoveCharacters {
+                    characters: Some(_),
+                    characters_file: Some(_),
+                } => {
+                    return Err(PipelineError::ConflictingOptions {
+                        step: "remove_characters",
+                        options: ["characters", "characters_file"],
+                    });
+                }
+                StepConfig::RemoveCharacters {
+                    characters: Some(characters),
+                    ..
+                } => Box::new(RemoveCharacters {
+                    characters: characters.chars().collect(),
+                }),
+                StepConfig::RemoveCharacters {
+                    characters_file: Some(path),
+                    ..
+                } => Box::new(RemoveCharacters::from_file(path)?),
+                StepConfig::RemoveCharacters { .. } => Box::new(RemoveCharacters::unwanted()),
+                StepConfig::SmallKana {
+                    policy,
+                    mappings_file: Some(path),
+                } => Box::new(ApplySmallKanaPolicy::from_file(policy, path)?),
+                StepConfig::SmallKana { policy, .. } => Box::new(ApplySmallKanaPolicy::new(policy)),
+                StepConfig::Nfkc => Box::new(NormaliseNfkc),
+                StepConfig::Choonpu { policy } => Box::new(ApplyChoonpuPolicy { policy }),
+            });
+        }
+
+        Ok(pipeline)
+    }
+
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, PipelineError> {
+        CleaningPipeline::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    /// Adds `step` to the end of the pipeline.
+    pub fn step(mut self, step: impl CleaningStep + 'static) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    /// Adds `step` so that it runs `position`th, counting from 0.
+    ///
+    /// Panics if `position` is greater than the number of steps.
+    pub fn insert(&mut self, position: usize, step: impl CleaningStep + 'static) {
+        self.steps.insert(position, Box::new(step));
+    }
+
+    /// Takes out the first step called `name`, if there is one.
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn CleaningStep>> {
+        let position = self.steps.iter().position(|step| step.name() == name)?;
+        Some(self.steps.remove(position))
+    }
+
+    /// The names of the steps, in the order they run.
+    pub fn step_names(&self) -> Vec<&'static str> {
+        self.steps.iter().map(|step| step.name()).collect()
+    }
+
+    /// Runs every step on `text` in turn.
+    pub fn apply(&self, text: &str) -> Result<String, PipelineError> {
+        self.steps
+            .iter()
+            .try_fold(text.to_string(), |text, step| step.apply(&text))
+    }
+
+    /// Runs every step on the lines of `unit`, joined by `\n`. An unbalanced
+    /// parenthesis error gives the unit’s index.
+    pub fn clean_unit(&self, unit: &SubtitleUnit) -> Result<String, PipelineError> {
+        self.apply(&unit.lines.join("\n"))
+            .map_err(|error| match error {
+                PipelineError::Parenthesis(mut unbalanced) => {
+                    unbalanced.unit_index = Some(unit.index.value());
+                    PipelineError::Parenthesis(unbalanced)
+                }
+                other => other,
+            })
+    }
+}
+
+/// Parses `text` into segments, drops those matching `unwanted` and writes the
+/// rest back out. Lines with nothing to drop are kept exactly as they were,
+/// since [`render_segments`] doesn’t always reproduce the original text.
+fn without_segments(text: &str, unwanted: impl Fn(&Segment) -> bool) -> String {
+    let lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    let segments = parse_segments(&lines);
+
+    let cleaned: Vec<String> = segments
+        .split(|segment| *segment == Segment::LineBreak)
+        .zip(&lines)
+        .map(|(line, original)| {
+            if line.iter().any(&unwanted) {
+                let kept: Vec<Segment> = line.iter().filter(|s| !unwanted(s)).cloned().collect();
+                render_segments(&kept)
+            } else {
+                original.clone()
+            }
+        })
+        .collect();
+
+    cleaned.join("\n")
+}
+
+/// The vowel of a kana, in the same script, or `None` for kana without one
+/// (`ん`, `っ`) and everything that isn’t kana.
+fn vowel_of(kana: char) -> Option<char> {
+    const VOWELS: [(char, &str); 5] = [
+        ('あ', "あぁかがさざただなはばぱまやゃらわゎ"),
+        ('い', "いぃきぎしじちぢにひびぴみりゐ"),
+        ('う', "うぅくぐすずつづぬふぶぷむゆゅるゔ"),
+        ('え', "えぇけげせぜてでねへべぺめれゑ"),
+        ('お', "おぉこごそぞとどのほぼぽもよょろを"),
+    ];
+    // Katakana sit 0x60 code points above the matching hiragana.
+    let is_katakana = ('ァ'..='ヴ').contains(&kana);
+    let hiragana = if is_katakana {
+        char::from_u32(kana as u32 - 0x60)?
+    } else {
+        kana
+    };
+
+    let (vowel, _) = VOWELS
+        .iter()
+        .find(|(_, members)| members.contains(hiragana))?;
+    if is_katakana {
+        char::from_u32(*vowel as u32 + 0x60)
+    } else {
+        Some(*vowel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::cleaning::clean_subtitles;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_steps_run_in_order() {
+        let text = "（狡噛(こうがみ)）コーヒー（足音）";
+
+        let labels_kept = CleaningPipeline::new().step(StripSoundEffects);
+        assert_eq!(
+            labels_kept.apply(text).unwrap(),
+            "（狡噛(こうがみ)）コーヒー"
+        );
+
+        let mut pipeline =
+            CleaningPipeline::new()
+                .step(StripSpeakerLabels)
+                .step(ApplyChoonpuPolicy {
+                    policy: ChoonpuPolicy::ExpandVowel,
+                });
+        assert_eq!(pipeline.apply(text).unwrap(), "コオヒイ（足音）");
+
+        // NFKC turns （） into (), which the segment steps after it no longer
+        // treat as labels.
+        pipeline.insert(0, NormaliseNfkc);
+        assert_eq!(
+            pipeline.step_names(),
+            vec!["nfkc", "strip_speaker_labels", "choonpu"]
+        );
+        assert_eq!(
+            pipeline.apply(text).unwrap(),
+            "(狡噛(こうがみ))コオヒイ(足音)"
+        );
+
+        assert!(pipeline.remove("nfkc").is_some());
+        assert!(pipeline.remove("nfkc").is_none());
+    }
+
+    #[test]
+    fn test_small_kana_policies() {
+        let apply = |policy| ApplySmallKanaPolicy::new(policy).apply("ちょっと").unwrap();
+        assert_eq!(apply(SmallKanaPolicy::Keep), "ちょっと");
+        assert_eq!(apply(SmallKanaPolicy::Convert), "ちよつと");
+        assert_eq!(apply(SmallKanaPolicy::Remove), "ちと");
+    }
+
+    #[test]
+    fn test_pipeline_from_toml() {
+        let config = r#"
+            [[step]]
+            name = "strip_sound_effects"
+
+            [[step]]
+            name = "strip_parentheses"
+            unbalanced = "auto_close"
+
+            [[step]]
+            name = "remove_characters"
+            characters = "…"
+
+            [[step]]
+            name = "choonpu"
+        "#;
+        let pipeline = CleaningPipeline::from_toml_str(config).unwrap();
+        assert_eq!(
+            pipeline.step_names(),
+            vec![
+                "strip_sound_effects",
+                "strip_parentheses",
+                "remove_characters",
+                "choonpu"
+            ]
+        );
+
+        let units = SubtitleParser::new()
+            .read_str(
+                "2
+00:00:46,921 --> 00:00:50,925
+（狡噛(こうがみ)）フゥ～…
+",
+            )
+            .unwrap();
+        assert_eq!(pipeline.clean_unit(&units[0]).unwrap(), "フゥ～");
+
+        assert!(matches!(
+            CleaningPipeline::from_toml_str("[[step]]\nname = \"shout\"\n"),
+            Err(PipelineError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_reference_tables_can_be_overridden() {
+        let config = "[[step]]\nname = \"remove_characters\"\n\
+            characters_file = \"data/raw/unwanted_characters.txt\"\n";
+        let from_file = CleaningPipeline::from_toml_str(config).unwrap();
+        let bundled = CleaningPipeline::new().step(RemoveCharacters::unwanted());
+        assert_eq!(
+            from_file.apply("えっと… あの？").unwrap(),
+            bundled.apply("えっと… あの？").unwrap()
+        );
+
+        let config = "[[step]]\nname = \"small_kana\"\nmappings_file = \"missing.json\"\n";
+        assert!(matches!(
+            CleaningPipeline::from_toml_str(config),
+            Err(PipelineError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_characters_and_characters_file_conflict() {
+        let config = "[[step]]\nname = \"remove_characters\"\ncharacters = \"…\"\n\
+            characters_file = \"data/raw/unwanted_characters.txt\"\n";
+        assert!(matches!(
+            CleaningPipeline::from_toml_str(config),
+            Err(PipelineError::ConflictingOptions {
+                step: "remove_characters",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_standard_pipeline_matches_clean_subtitles() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let pipeline = CleaningPipeline::standard(ParenthesisPolicy::Fail);
+
+        let cleaned = pipeline.clean_unit(&units[1]).unwrap();
+        assert_eq!(cleaned, "フウ");
+        assert_eq!(
+            clean_subtitles(&units[1].lines.join("\n"), ParenthesisPolicy::Fail).unwrap(),
+            cleaned
+        );
+    }
+}
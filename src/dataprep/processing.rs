@@ -1,10 +1,226 @@
 /*
 Processing here involves labelling (cleaned) characters as either kanji, hiragana
-or katakana. Modelling involves enriching characters with metadata information,
-such as their total number of occurrences in the episode, label/category,
-membership status in the jōyō kanji and so on. (Use a single schema for all
-categories of characters.)
+or katakana. Modelling, in modelling.rs, involves enriching characters with
+metadata information, such as their total number of occurrences in the episode,
+label/category, membership status in the jōyō kanji and so on. (Use a single
+schema for all categories of characters.)
 
 Then, convert the structs created above into a dataframe as preparation to
 present results.
  */
+
+use crate::types::segment::{is_kanji, spoken_text};
+use crate::types::subtitle_unit::SubtitleUnit;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The kind of writing a character belongs to. Classes follow Unicode blocks
+/// and general categories, so `〜` (wave dash) is punctuation while `～`
+/// (full-width tilde) is a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptClass {
+    /// CJK ideographs and the marks that stand in for kanji (`々`, `〻`, `〆`
+    /// and `〇`), as [`is_kanji`](crate::types::segment::is_kanji) defines them.
+    Kanji,
+    Hiragana,
+    /// Full- and half-width katakana, including small forms such as `ヶ`.
+    Katakana,
+    /// _Chōonpu_, `ー` or half-width `ｰ`.
+    ProlongedSoundMark,
+    /// ASCII letters and digits, and accented Latin letters.
+    Latin,
+    /// Full-width letters and digits, such as `ＫＴ` or `１２`.
+    FullWidthAlphanumeric,
+    /// Japanese and Western punctuation: `、。「」（）！？…―・` and so on.
+    Punctuation,
+    /// Everything else that isn’t a letter or digit, such as `♪`, `～` or `★`.
+    Symbol,
+    Whitespace,
+    /// Letters of other scripts, and control characters.
+    Other,
+}
+
+/// Counts of characters per [`ScriptClass`]. Whitespace isn’t counted, so
+/// proportions are of the visible characters.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ScriptBreakdown {
+    counts: BTreeMap<ScriptClass, usize>,
+}
+
+impl ScriptBreakdown {
+    pub fn from_text(text: &str) -> Self {
+        let mut breakdown = ScriptBreakdown::default();
+        for c in text.chars() {
+            breakdown.add(c);
+        }
+        breakdown
+    }
+
+    pub fn add(&mut self, c: char) {
+        let class = classify(c);
+        if class != ScriptClass::Whitespace {
+            *self.counts.entry(class).or_default() += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &ScriptBreakdown) {
+        for (class, count) in &other.counts {
+            *self.counts.entry(*class).or_default() += count;
+        }
+    }
+
+    pub fn count(&self, class: ScriptClass) -> usize {
+        self.counts.get(&class).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// The share of characters in `class`, from `0.0` to `1.0`; `0.0` if there
+    /// are no characters at all.
+    pub fn proportion(&self, class: ScriptClass) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.count(class) as f64 / total as f64,
+        }
+    }
+
+    /// Each class with at least one character, in [`ScriptClass`] order.
+    pub fn iter(&self) -> impl Iterator<Item = (ScriptClass, usize)> + '_ {
+        self.counts.iter().map(|(class, count)| (*class, *count))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnitBreakdown {
+    pub unit_index: u32,
+    pub breakdown: ScriptBreakdown,
+}
+
+/// Script breakdowns for every unit of an episode and for the episode as a
+/// whole. Serialises to JSON with serde, e.g. via [`EpisodeBreakdown::to_json`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EpisodeBreakdown {
+    pub units: Vec<UnitBreakdown>,
+    pub total: ScriptBreakdown,
+}
+
+impl EpisodeBreakdown {
+    pub fn from_units(units: &[SubtitleUnit]) -> Self {
+        //! Classifies the dialogue of each unit: speaker labels, sound effects,
+        //! music cues and furigana readings are left out, while the words the
+        //! furigana gloss are kept. To classify some other text, such as the
+        //! output of a [`CleaningPipeline`](crate::dataprep::pipeline::CleaningPipeline),
+        //! use [`ScriptBreakdown::from_text`].
+
+        let mut episode = EpisodeBreakdown::default();
+
+        for unit in units {
+            let breakdown = ScriptBreakdown::from_text(&spoken_text(&unit.segments()));
+            episode.total.merge(&breakdown);
+            episode.units.push(UnitBreakdown {
+                unit_index: unit.index.value(),
+                breakdown,
+            });
+        }
+
+        episode
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+pub fn classify(c: char) -> ScriptClass {
+    //! The [`ScriptClass`] of a single character.
+
+    match c {
+        'ー' | 'ｰ' => ScriptClass::ProlongedSoundMark,
+        _ if is_kanji(c) => ScriptClass::Kanji,
+        '\u{3041}'..='\u{309F}' => ScriptClass::Hiragana,
+        // ゠ and ・ sit in the katakana block but are punctuation.
+        '゠' | '・' | '･' => ScriptClass::Punctuation,
+        '\u{30A1}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => {
+            ScriptClass::Katakana
+        }
+        '０'..='９' | 'Ａ'..='Ｚ' | 'ａ'..='ｚ' => ScriptClass::FullWidthAlphanumeric,
+        _ if c.is_whitespace() => ScriptClass::Whitespace,
+        _ if c.is_ascii_alphanumeric() => ScriptClass::Latin,
+        '\u{00C0}'..='\u{024F}' if c.is_alphabetic() => ScriptClass::Latin,
+        _ if is_punctuation(c) => ScriptClass::Punctuation,
+        _ if c.is_alphanumeric() || c.is_control() => ScriptClass::Other,
+        _ => ScriptClass::Symbol,
+    }
+}
+
+fn is_punctuation(c: char) -> bool {
+    // ASCII and full-width punctuation, less the math and currency symbols
+    // (and their full-width forms) that Unicode counts as symbols.
+    const SYMBOLS: &str = "$+<=>^`|~＄＋＜＝＞＾｀｜～￠￡￢￣￤￥￦";
+
+    let is_ascii_or_full_width = c.is_ascii_punctuation()
+        || matches!(c, '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}'
+            | '\u{FF3B}'..='\u{FF40}' | '\u{FF5B}'..='\u{FF65}');
+
+    (is_ascii_or_full_width && !SYMBOLS.contains(c))
+        // CJK punctuation: 、。「」『』【】〜 and so on (less 〄 and 〒, which
+        // are symbols).
+        || matches!(c, '\u{3001}'..='\u{3003}' | '\u{3008}'..='\u{3011}'
+            | '\u{3014}'..='\u{301F}' | '\u{3030}' | '\u{303D}')
+        // General punctuation: ―, …, ‥, ‘’, “” and so on.
+        || matches!(c, '\u{2010}'..='\u{2027}' | '\u{2030}'..='\u{205E}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_classify() {
+        let classes = |text: &str| text.chars().map(classify).collect::<Vec<_>>();
+
+        assert_eq!(classes("狡々𠀋𪜀"), vec![ScriptClass::Kanji; 4]);
+        assert_eq!(classes("あゝ"), vec![ScriptClass::Hiragana; 2]);
+        assert_eq!(classes("カヶｶ"), vec![ScriptClass::Katakana; 3]);
+        assert_eq!(classes("ーｰ"), vec![ScriptClass::ProlongedSoundMark; 2]);
+        assert_eq!(classes("Ké9"), vec![ScriptClass::Latin; 3]);
+        assert_eq!(classes("Ｋ１"), vec![ScriptClass::FullWidthAlphanumeric; 2]);
+        assert_eq!(
+            classes("、。「！？…―・（"),
+            vec![ScriptClass::Punctuation; 9]
+        );
+        assert_eq!(classes("♪～★"), vec![ScriptClass::Symbol; 3]);
+        assert_eq!(classes(" 　"), vec![ScriptClass::Whitespace; 2]);
+        assert_eq!(classes("한"), vec![ScriptClass::Other]);
+    }
+
+    #[test]
+    fn test_episode_breakdown() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let episode = EpisodeBreakdown::from_units(&units);
+
+        assert_eq!(episode.units.len(), units.len());
+        let summed: usize = episode
+            .units
+            .iter()
+            .map(|unit| unit.breakdown.total())
+            .sum();
+        assert_eq!(summed, episode.total.total());
+
+        // Unit 2 is （狡噛(こうがみ)）フゥ～…
+        let unit = &episode.units[1].breakdown;
+        assert_eq!(unit.count(ScriptClass::Kanji), 0);
+        assert_eq!(unit.count(ScriptClass::Katakana), 2);
+        assert_eq!(unit.count(ScriptClass::Symbol), 1);
+
+        assert!(episode.total.proportion(ScriptClass::Hiragana) > 0.3);
+        assert_eq!(episode.total.count(ScriptClass::Other), 0);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::types::framerate::{Framerate, div_round};
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::Timestamp;
+use crate::types::timing::Timing;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// What to do when retiming would move a timestamp below `00:00:00,000` (or
+/// beyond [`Timestamp::MAX`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfRangePolicy {
+    /// Clamp to zero (or to [`Timestamp::MAX`]).
+    #[default]
+    Clamp,
+    /// Fail with a [`RetimingError`].
+    Fail,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RetimingError {
+    /// A timestamp of the unit with this index would go below zero.
+    NegativeTimestamp(u32),
+    /// A timestamp of the unit with this index would go beyond [`Timestamp::MAX`].
+    TimestampOverflow(u32),
+    /// Both anchors of a resync have the same subtitle time, so no rate can be
+    /// worked out from them.
+    IdenticalAnchors,
+    /// The anchors’ actual times are equal or in the opposite order to their
+    /// subtitle times, which would end every unit before it starts.
+    ReversedAnchors,
+}
+
+impl Display for RetimingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetimingError::NegativeTimestamp(index) => {
+                write!(f, "Unit {} would start or end before 00:00:00,000", index)
+            }
+            RetimingError::TimestampOverflow(index) => write!(
+                f,
+                "Unit {} would start or end after {}",
+                index,
+                Timestamp::MAX
+            ),
+            RetimingError::IdenticalAnchors => {
+                write!(f, "Resync anchors must have different subtitle times")
+            }
+            RetimingError::ReversedAnchors => write!(
+                f,
+                "Resync anchors’ actual times must be in the same order as their subtitle times"
+            ),
+        }
+    }
+}
+
+impl Error for RetimingError {}
+
+/// A point where a subtitle time is known to correspond to a time in the
+/// actual video.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Anchor {
+    pub subtitle: Timestamp,
+    pub actual: Timestamp,
+}
+
+pub fn shift(
+    units: &[SubtitleUnit],
+    offset_millis: i64,
+    policy: OutOfRangePolicy,
+) -> Result<Vec<SubtitleUnit>, RetimingError> {
+    //! Moves every unit by the same signed number of milliseconds; positive
+    //! values make subtitles appear later.
+
+    retime(units, policy, |millis| millis + offset_millis as i128)
+}
+
+pub fn resync(
+    units: &[SubtitleUnit],
+    first: Anchor,
+    second: Anchor,
+    policy: OutOfRangePolicy,
+) -> Result<Vec<SubtitleUnit>, RetimingError> {
+    //! Maps every timestamp through the straight line running through both
+    //! anchors. This corrects a constant offset and a constant rate difference
+    //! (drift) at the same time. Anchors are best taken far apart, e.g. one
+    //! early line and one late line of the episode.
+    //!
+    //! The maths is done in integer milliseconds and rounded to the nearest
+    //! millisecond once per timestamp, so no error builds up over a track.
+
+    let s1 = first.subtitle.to_millis() as i128;
+    let s2 = second.subtitle.to_millis() as i128;
+    let a1 = first.actual.to_millis() as i128;
+    let a2 = second.actual.to_millis() as i128;
+
+    if s1 == s2 {
+        return Err(RetimingError::IdenticalAnchors);
+    }
+    if (a2 - a1).signum() != (s2 - s1).signum() {
+        return Err(RetimingError::ReversedAnchors);
+    }
+
+    retime(units, policy, |millis| {
+        a1 + div_round((millis - s1) * (a2 - a1), s2 - s1)
+    })
+}
+
+pub fn convert_framerate(
+    units: &[SubtitleUnit],
+    source: Framerate,
+    target: Framerate,
+    policy: OutOfRangePolicy,
+) -> Result<Vec<SubtitleUnit>, RetimingError> {
+    //! Rescales every timestamp for a video played back at a different rate,
+    //! keeping each subtitle on the same frame. Going from 23.976 to 25 fps (a
+    //! PAL speed-up), for example, makes every timestamp about 4% earlier.
+    //!
+    //! The new time is `t × source ÷ target`, worked out exactly with the
+    //! framerates’ fractions from each original timestamp and rounded to the
+    //! nearest millisecond once, so no rounding error accumulates.
+
+    let numerator = source.numerator() as i128 * target.denominator() as i128;
+    let denominator = source.denominator() as i128 * target.numerator() as i128;
+
+    retime(units, policy, |millis| {
+        div_round(millis * numerator, denominator)
+    })
+}
+
+/// Applies `map` to the start and end of every unit (in milliseconds) and
+/// checks the results against `policy`.
+pub(crate) fn retime(
+    units: &[SubtitleUnit],
+    policy: OutOfRangePolicy,
+    map: impl Fn(i128) -> i128,
+) -> Result<Vec<SubtitleUnit>, RetimingError> {
+    let max = Timestamp::MAX.to_millis() as i128;
+
+    let convert = |timestamp: Timestamp, index: u32| -> Result<Timestamp, RetimingError> {
+        let mapped = map(timestamp.to_millis() as i128);
+        let in_range = match (policy, mapped) {
+            (OutOfRangePolicy::Fail, m) if m < 0 => {
+                return Err(RetimingError::NegativeTimestamp(index));
+            }
+            (OutOfRangePolicy::Fail, m) if m > max => {
+                return Err(RetimingError::TimestampOverflow(index));
+            }
+            (_, m) => m.clamp(0, max),
+        };
+        // `in_range` is within 0..=max, so this can’t fail.
+        Ok(Timestamp::from_millis(in_range as u64).expect("Clamped timestamp is in range"))
+    };
+
+    units
+        .iter()
+        .map(|unit| {
+            let index = unit.index.value();
+            Ok(SubtitleUnit {
+                index: unit.index.clone(),
+                timing: Timing {
+                    start: convert(unit.timing.start, index)?,
+                    end: convert(unit.timing.end, index)?,
+                },
+                lines: unit.lines.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    const INPUT: &str =
+        "1\n00:00:01,000 --> 00:00:02,000\nHello\n\n2\n00:10:00,000 --> 00:10:02,500\nWorld\n";
+
+    fn timestamp(s: &str) -> Timestamp {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_shift_clamps_or_fails() {
+        let units = SubtitleParser::new().read_str(INPUT).unwrap();
+
+        let shifted = shift(&units, -1_500, OutOfRangePolicy::Clamp).unwrap();
+        assert_eq!(
+            shifted[0].timing.to_string(),
+            "00:00:00,000 --> 00:00:00,500"
+        );
+        assert_eq!(
+            shifted[1].timing.to_string(),
+            "00:09:58,500 --> 00:10:01,000"
+        );
+
+        assert_eq!(
+            shift(&units, -1_500, OutOfRangePolicy::Fail),
+            Err(RetimingError::NegativeTimestamp(1))
+        );
+    }
+
+    #[test]
+    fn test_convert_framerate_does_not_accumulate_error() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let ntsc_film = "23.976".parse::<Framerate>().unwrap();
+        assert_eq!(ntsc_film, Framerate::NTSC_FILM);
+
+        let pal =
+            convert_framerate(&units, ntsc_film, Framerate::PAL, OutOfRangePolicy::Fail).unwrap();
+        // 00:21:48,140 × (24000/1001) ÷ 25 = 00:20:54,560.
+        assert_eq!(pal[304].timing.end.to_string(), "00:20:54,560");
+
+        // Going there and back rounds twice, so a timestamp may be a millisecond
+        // off, but never more, however late in the episode it is.
+        let back =
+            convert_framerate(&pal, Framerate::PAL, ntsc_film, OutOfRangePolicy::Fail).unwrap();
+        for (original, converted) in units.iter().zip(&back) {
+            for (a, b) in [
+                (original.timing.start, converted.timing.start),
+                (original.timing.end, converted.timing.end),
+            ] {
+                assert!(a.to_millis().abs_diff(b.to_millis()) <= 1, "{} vs {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resync_corrects_offset_and_drift() {
+        let units = SubtitleParser::new().read_str(INPUT).unwrap();
+        // Subtitles run 0.1% fast and 2 s early.
+        let first = Anchor {
+            subtitle: timestamp("00:00:01,000"),
+            actual: timestamp("00:00:03,001"),
+        };
+        let second = Anchor {
+            subtitle: timestamp("00:10:00,000"),
+            actual: timestamp("00:10:02,600"),
+        };
+
+        let resynced = resync(&units, first, second, OutOfRangePolicy::Fail).unwrap();
+        assert_eq!(resynced[0].timing.start, first.actual);
+        assert_eq!(resynced[1].timing.start, second.actual);
+        assert_eq!(resynced[1].timing.end.to_string(), "00:10:05,103");
+    }
+
+    #[test]
+    fn test_resync_rejects_reversed_anchors() {
+        let units = SubtitleParser::new().read_str(INPUT).unwrap();
+        let anchor = |subtitle, actual| Anchor {
+            subtitle: timestamp(subtitle),
+            actual: timestamp(actual),
+        };
+
+        let reversed = resync(
+            &units,
+            anchor("00:01:00,000", "00:20:00,000"),
+            anchor("00:20:00,000", "00:01:00,000"),
+            OutOfRangePolicy::Clamp,
+        );
+        assert_eq!(reversed, Err(RetimingError::ReversedAnchors));
+
+        let flat = resync(
+            &units,
+            anchor("00:01:00,000", "00:05:00,000"),
+            anchor("00:20:00,000", "00:05:00,000"),
+            OutOfRangePolicy::Clamp,
+        );
+        assert_eq!(flat, Err(RetimingError::ReversedAnchors));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::dataprep::ass;
+use crate::dataprep::ingestion::{PathError, SafeFilePath};
+use crate::dataprep::normalisation::{LineEnding, NormalisedLine, NormalisedLines};
+use crate::types::srt_index::SrtIndex;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::{Timestamp, TimestampError};
+use crate::types::timing::Timing;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Write};
+use std::path::Path;
+
+/// Separates the lines of a subtitle in SubViewer text.
+const LINE_BREAK: &str = "[br]";
+
+const HEADER: [&str; 12] = [
+    "[INFORMATION]",
+    "[TITLE]",
+    "[AUTHOR]",
+    "[SOURCE]",
+    "[PRG]",
+    "[FILEPATH]",
+    "[DELAY]0",
+    "[CD TRACK]0",
+    "[COMMENT]",
+    "[END INFORMATION]",
+    "[SUBTITLE]",
+    "[COLF]&HFFFFFF,[STYLE]no,[SIZE]18,[FONT]Arial",
+];
+
+#[derive(Debug)]
+pub enum SubViewerError {
+    Io(io::Error),
+    Path(PathError),
+    /// Text came before any timing line.
+    MissingTiming {
+        line_number: usize,
+    },
+    /// A timing line’s end is earlier than its start.
+    ReversedTiming {
+        line_number: usize,
+    },
+    Timestamp {
+        line_number: usize,
+        error: TimestampError,
+    },
+}
+
+impl Display for SubViewerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubViewerError::Io(e) => write!(f, "I/O error: {}", e),
+            SubViewerError::Path(e) => write!(f, "Path error: {}", e),
+            SubViewerError::MissingTiming { line_number } => {
+                write!(f, "Line {}: subtitle text has no timing line", line_number)
+            }
+            SubViewerError::ReversedTiming { line_number } => {
+                write!(f, "Line {}: start is later than end", line_number)
+            }
+            SubViewerError::Timestamp { line_number, error } => {
+                write!(f, "Line {}: invalid timestamp: {:?}", line_number, error)
+            }
+        }
+    }
+}
+
+impl Error for SubViewerError {}
+
+impl From<io::Error> for SubViewerError {
+    fn from(error: io::Error) -> Self {
+        SubViewerError::Io(error)
+    }
+}
+
+impl From<PathError> for SubViewerError {
+    fn from(error: PathError) -> Self {
+        SubViewerError::Path(error)
+    }
+}
+
+pub fn read_subviewer<R: BufRead>(reader: R) -> Result<Vec<SubtitleUnit>, SubViewerError> {
+    //! Reads SubViewer 2.0: a `[INFORMATION]` header, then blocks of a
+    //! `hh:mm:ss.cc,hh:mm:ss.cc` timing line and text, separated by blank lines.
+    //! `[br]` inside the text starts a new line. Header lines (anything in
+    //! square brackets before the first timing line) are skipped.
+
+    let mut lines = NormalisedLines::new(reader);
+    let mut units: Vec<SubtitleUnit> = Vec::new();
+    let mut in_block = false;
+
+    while let Some(line) = lines.read_line()? {
+        let line_number = line.line_number;
+
+        if line.text.is_empty() {
+            in_block = false;
+        } else if in_block {
+            let unit = units.last_mut().expect("A block has a unit");
+            unit.lines
+                .extend(line.text.split(LINE_BREAK).map(str::to_string));
+        } else if let Some(timing) = parse_timing(&line)? {
+            units.push(SubtitleUnit {
+                index: SrtIndex::from(units.len() as u32 + 1),
+                timing,
+                lines: Vec::new(),
+            });
+            in_block = true;
+        } else if !(units.is_empty() && line.text.starts_with('[')) {
+            return Err(SubViewerError::MissingTiming { line_number });
+        }
+    }
+
+    Ok(units)
+}
+
+pub fn read_subviewer_str(content: &str) -> Result<Vec<SubtitleUnit>, SubViewerError> {
+    read_subviewer(Cursor::new(content))
+}
+
+pub fn read_subviewer_file(path: impl AsRef<Path>) -> Result<Vec<SubtitleUnit>, SubViewerError> {
+    // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::with_extensions(raw_path, &["sub"])?;
+
+    read_subviewer(BufReader::new(File::open(checked_path)?))
+}
+
+/// `None` if `line` doesn’t look like a timing line at all; an error if it does
+/// but a timestamp is invalid.
+fn parse_timing(line: &NormalisedLine) -> Result<Option<Timing>, SubViewerError> {
+    let looks_like_timing = line.text.starts_with(|c: char| c.is_ascii_digit())
+        && line.text.contains(':')
+        && line.text.contains(',');
+    let Some((start, end)) = line.text.split_once(',').filter(|_| looks_like_timing) else {
+        return Ok(None);
+    };
+
+    let timestamp = |raw: &str| {
+        ass::parse_timestamp(raw.trim()).map_err(|error| SubViewerError::Timestamp {
+            line_number: line.line_number,
+            error,
+        })
+    };
+    let (start, end) = (timestamp(start)?, timestamp(end)?);
+    if start > end {
+        return Err(SubViewerError::ReversedTiming {
+            line_number: line.line_number,
+        });
+    }
+    Ok(Some(Timing { start, end }))
+}
+
+/// Writes [`SubtitleUnit`]s as SubViewer 2.0 to any `io::Write`. A blank
+/// `[INFORMATION]` header is written before the first unit. SubViewer times
+/// have centisecond precision, so timestamps are rounded to the nearest 10 ms.
+///
+/// ```text
+/// let mut writer = SubViewerWriter::new(File::create("out.sub")?);
+/// writer.write_units(&units)?;
+/// ```
+#[derive(Debug)]
+pub struct SubViewerWriter<W: Write> {
+    inner: W,
+    line_ending: LineEnding,
+    header_written: bool,
+}
+
+impl<W: Write> SubViewerWriter<W> {
+    /// Creates a writer using `\n` line endings.
+    pub fn new(inner: W) -> Self {
+        SubViewerWriter {
+            inner,
+            line_ending: LineEnding::Lf,
+            header_written: false,
+        }
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    pub fn write_unit(&mut self, unit: &SubtitleUnit) -> io::Result<()> {
+        let ending = self.line_ending.as_str();
+
+        if !self.header_written {
+            for line in HEADER {
+                write!(self.inner, "{}{}", line, ending)?;
+            }
+            self.header_written = true;
+        }
+
+        write!(
+            self.inner,
+            "{},{}{}{}{}{}",
+            ass::format_timestamp(unit.timing.start, 2),
+            ass::format_timestamp(unit.timing.end, 2),
+            ending,
+            unit.lines.join(LINE_BREAK),
+            ending,
+            ending
+        )
+    }
+
+    pub fn write_units<'a>(
+        &mut self,
+        units: impl IntoIterator<Item = &'a SubtitleUnit>,
+    ) -> io::Result<()> {
+        for unit in units {
+            self.write_unit(unit)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+pub fn to_subviewer_string(units: &[SubtitleUnit]) -> String {
+    //! Writes `units` as SubViewer 2.0 with `\n` line endings.
+
+    let mut writer = SubViewerWriter::new(Vec::new());
+    writer
+        .write_units(units)
+        .expect("Writing to a Vec can’t fail");
+
+    String::from_utf8(writer.into_inner()).expect("SubViewer output is always UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    #[test]
+    fn test_read_subviewer() {
+        let input = "[INFORMATION]\n[TITLE]Psycho-Pass\n[END INFORMATION]\n[SUBTITLE]\n\
+            00:00:12.85,00:00:24.56\n犯罪係数[br]測定中\n\n00:01:00.00,00:01:02.50\nシビュラ\n";
+        let units = read_subviewer_str(input).unwrap();
+
+        assert_eq!(units.len(), 2);
+        assert_eq!(units[0].timing.to_string(), "00:00:12,850 --> 00:00:24,560");
+        assert_eq!(units[0].lines, vec!["犯罪係数", "測定中"]);
+        assert_eq!(units[1].index.value(), 2);
+
+        assert!(matches!(
+            read_subviewer_str("00:00:01.00,00:00:02.00\nHi\n\nStray\n"),
+            Err(SubViewerError::MissingTiming { line_number: 4 })
+        ));
+        assert!(matches!(
+            read_subviewer_str("00:00:05.00,00:00:01.00\nHi\n"),
+            Err(SubViewerError::ReversedTiming { line_number: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_srt_episode_round_trips_to_the_centisecond() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let subviewer = to_subviewer_string(&units);
+        assert!(subviewer.starts_with("[INFORMATION]\n"));
+
+        let back = read_subviewer_str(&subviewer).unwrap();
+        assert_eq!(back.len(), units.len());
+        for (original, converted) in units.iter().zip(&back) {
+            assert_eq!(original.lines, converted.lines);
+            let drift = original
+                .timing
+                .end
+                .to_millis()
+                .abs_diff(converted.timing.end.to_millis());
+            assert!(drift <= 5);
+        }
+    }
+}
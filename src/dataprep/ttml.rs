@@ -0,0 +1,711 @@
+use crate::dataprep::ingestion::{PathError, SafeFilePath, decode_subtitle_bytes};
+use crate::types::srt_index::SrtIndex;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::{Timestamp, TimestampError};
+use crate::types::timing::Timing;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How deep `style` references may chain before they are taken to be a loop.
+const MAX_STYLE_DEPTH: usize = 16;
+
+/// A parsed TTML document (including the IMSC and DFXP profiles used for
+/// streaming). Each `<p>` becomes one [`TtmlCue`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TtmlDocument {
+    pub cues: Vec<TtmlCue>,
+}
+
+/// One `<p>` element. `lines` keeps the text as segments, split at `<br/>`,
+/// so ruby stays attached to its base; the unit’s lines hold the same text
+/// with ruby reduced to its base, which is how it reads on screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TtmlCue {
+    /// The `xml:id` of the `<p>`, if any.
+    pub id: Option<String>,
+    pub position: TtmlPosition,
+    pub lines: Vec<Vec<TtmlSegment>>,
+    pub unit: SubtitleUnit,
+}
+
+/// Where and how a cue is laid out, from its region and styles. Lengths are
+/// kept as written (`10% 80%`, `640px 40px`), since they mix units.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TtmlPosition {
+    /// The `xml:id` of the cue’s region.
+    pub region: Option<String>,
+    /// `tts:origin`: the top-left corner of the region.
+    pub origin: Option<String>,
+    /// `tts:extent`: the width and height of the region.
+    pub extent: Option<String>,
+    pub writing_mode: WritingMode,
+    /// `tts:textAlign`, e.g. `center` or `start`.
+    pub text_align: Option<String>,
+    /// `tts:displayAlign`, e.g. `after` for the bottom of a horizontal region.
+    pub display_align: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WritingMode {
+    #[default]
+    Horizontal,
+    /// `tbrl`: top to bottom, lines added right to left (tategaki).
+    VerticalRightToLeft,
+    /// `tblr`: top to bottom, lines added left to right.
+    VerticalLeftToRight,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TtmlSegment {
+    Text {
+        text: String,
+        /// `tts:textCombine="all"`: set upright in a single cell of vertical
+        /// text (tate-chu-yoko), as is done for short numbers like `12`.
+        combine_upright: bool,
+    },
+    Ruby {
+        base: String,
+        /// The reading, usually in hiragana.
+        text: String,
+        /// `tts:rubyPosition`, e.g. `over` or `outside`.
+        position: Option<String>,
+    },
+}
+
+#[derive(Debug)]
+pub enum TtmlError {
+    Io(io::Error),
+    Path(PathError),
+    Xml(roxmltree::Error),
+    /// A `<p>` has no `begin`, or neither an `end` nor a `dur`.
+    MissingTiming {
+        line_number: usize,
+    },
+    /// A time expression isn’t one TTML allows, or a parameter such as
+    /// `ttp:frameRate` isn’t a positive number.
+    InvalidTime {
+        line_number: usize,
+        value: String,
+    },
+    /// A `<p>` ends before it begins.
+    ReversedTiming {
+        line_number: usize,
+    },
+    Timestamp {
+        line_number: usize,
+        error: TimestampError,
+    },
+}
+
+impl Display for TtmlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TtmlError::Io(e) => write!(f, "I/O error: {}", e),
+            TtmlError::Path(e) => write!(f, "Path error: {}", e),
+            TtmlError::Xml(e) => write!(f, "XML error: {}", e),
+            TtmlError::MissingTiming { line_number } => {
+                write!(f, "Line {}: <p> has no begin and end", line_number)
+            }
+            TtmlError::InvalidTime { line_number, value } => {
+                write!(f, "Line {}: invalid time {}", line_number, value)
+            }
+            TtmlError::ReversedTiming { line_number } => {
+                write!(f, "Line {}: <p> ends before it begins", line_number)
+            }
+            TtmlError::Timestamp { line_number, error } => {
+                write!(f, "Line {}: invalid timestamp: {:?}", line_number, error)
+            }
+        }
+    }
+}
+
+impl Error for TtmlError {}
+
+impl From<io::Error> for TtmlError {
+    fn from(error: io::Error) -> Self {
+        TtmlError::Io(error)
+    }
+}
+
+impl From<PathError> for TtmlError {
+    fn from(error: PathError) -> Self {
+        TtmlError::Path(error)
+    }
+}
+
+impl From<roxmltree::Error> for TtmlError {
+    fn from(error: roxmltree::Error) -> Self {
+        TtmlError::Xml(error)
+    }
+}
+
+impl TtmlDocument {
+    /// The cues as plain units, dropping positioning and ruby readings.
+    pub fn units(&self) -> Vec<SubtitleUnit> {
+        self.cues.iter().map(|cue| cue.unit.clone()).collect()
+    }
+}
+
+impl TtmlSegment {
+    /// What the segment shows on the main line: the text, or the ruby base.
+    pub fn base_text(&self) -> &str {
+        match self {
+            TtmlSegment::Text { text, .. } => text,
+            TtmlSegment::Ruby { base, .. } => base,
+        }
+    }
+}
+
+/// Exact rates from the `ttp:` parameters on `<tt>`, for frame and tick times.
+#[derive(Debug, Clone, Copy)]
+struct TimeBase {
+    /// Frames per second as `(numerator, denominator)`.
+    frame_rate: (u128, u128),
+    ticks_per_second: u128,
+}
+
+/// Lookups shared while walking the document.
+struct Context<'a, 'input> {
+    document: &'a Document<'input>,
+    styles: HashMap<&'a str, Node<'a, 'input>>,
+    regions: HashMap<&'a str, Node<'a, 'input>>,
+    time_base: TimeBase,
+}
+
+pub fn read_ttml_str(content: &str) -> Result<TtmlDocument, TtmlError> {
+    //! Reads a TTML document. Styles and regions are resolved through `style`
+    //! references, and times may be clock times (`00:00:12.846`,
+    //! `00:00:12:20` in frames) or offsets (`12.846s`, `128460000t` in ticks).
+    //!
+    //! `begin` on a `<body>` or `<div>` offsets the times of the `<p>`s inside.
+
+    let document = Document::parse(content.trim_start_matches('\u{FEFF}'))?;
+    let root = document.root_element();
+
+    let elements_named = |name: &str| {
+        document
+            .descendants()
+            .filter(|node| node.is_element() && node.tag_name().name() == name)
+            .filter_map(|node| Some((attribute(node, "id")?, node)))
+            .collect::<HashMap<_, _>>()
+    };
+    let context = Context {
+        document: &document,
+        styles: elements_named("style"),
+        regions: elements_named("region"),
+        time_base: time_base(root, &document)?,
+    };
+
+    let mut cues = Vec::new();
+    for paragraph in root
+        .descendants()
+        .filter(|node| node.is_element() && node.tag_name().name() == "p")
+    {
+        let position = cues.len() as u32 + 1;
+        cues.push(read_cue(paragraph, position, &context)?);
+    }
+
+    Ok(TtmlDocument { cues })
+}
+
+pub fn read_ttml_file(path: impl AsRef<Path>) -> Result<TtmlDocument, TtmlError> {
+    // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::with_extensions(raw_path, &["ttml", "dfxp", "xml"])?;
+    let (content, _) = decode_subtitle_bytes(&fs::read(checked_path)?, None);
+
+    read_ttml_str(&content)
+}
+
+/// The value of the attribute with local name `name`, in any namespace. TTML
+/// has gone through several namespace URIs (DFXP used `ttaf1`), so matching on
+/// the prefix-less name is what keeps older files readable.
+fn attribute<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attributes()
+        .find(|attribute| attribute.name() == name)
+        .map(|attribute| attribute.value())
+}
+
+/// Like [`attribute`], but also looks through the styles `node` references.
+fn styled<'a>(node: Node<'a, '_>, name: &str, context: &Context<'a, '_>) -> Option<&'a str> {
+    fn lookup<'a>(
+        node: Node<'a, '_>,
+        name: &str,
+        context: &Context<'a, '_>,
+        depth: usize,
+    ) -> Option<&'a str> {
+        if let Some(value) = attribute(node, name) {
+            return Some(value);
+        } else if depth >= MAX_STYLE_DEPTH {
+            return None;
+        }
+        attribute(node, "style")?
+            .split_whitespace()
+            .filter_map(|id| context.styles.get(id))
+            .find_map(|style| lookup(*style, name, context, depth + 1))
+    }
+
+    lookup(node, name, context, 0)
+}
+
+fn line_number(node: Node, context: &Context) -> usize {
+    context.document.text_pos_at(node.range().start).row as usize
+}
+
+fn time_base(root: Node, document: &Document) -> Result<TimeBase, TtmlError> {
+    let line_number = document.text_pos_at(root.range().start).row as usize;
+    let invalid = |value: &str| TtmlError::InvalidTime {
+        line_number,
+        value: value.to_string(),
+    };
+    let positive = |value: &str| -> Result<u128, TtmlError> {
+        value
+            .trim()
+            .parse::<u128>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| invalid(value))
+    };
+
+    let frames = attribute(root, "frameRate").map(positive).transpose()?;
+    let (multiplier_numerator, multiplier_denominator) =
+        match attribute(root, "frameRateMultiplier") {
+            Some(raw) => match raw.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [numerator, denominator] => (positive(numerator)?, positive(denominator)?),
+                _ => return Err(invalid(raw)),
+            },
+            None => (1, 1),
+        };
+    let ticks_per_second = match attribute(root, "tickRate") {
+        Some(raw) => positive(raw)?,
+        None => frames.unwrap_or(1),
+    };
+
+    // Only a multiplier can push the frame rate past u128.
+    let frame_numerator = frames
+        .unwrap_or(30)
+        .checked_mul(multiplier_numerator)
+        .ok_or_else(|| invalid(attribute(root, "frameRateMultiplier").unwrap_or_default()))?;
+
+    Ok(TimeBase {
+        frame_rate: (frame_numerator, multiplier_denominator),
+        ticks_per_second,
+    })
+}
+
+/// Parses a time expression into seconds as an exact `(numerator,
+/// denominator)` fraction, or `None` if it doesn’t fit in one.
+fn parse_time(value: &str, time_base: TimeBase) -> Option<(u128, u128)> {
+    let value = value.trim();
+
+    if value.contains(':') {
+        // hh:mm:ss, hh:mm:ss.fraction or hh:mm:ss:frames(.subframes)
+        let fields: Vec<&str> = value.split(':').collect();
+        let (hours, minutes, seconds, frames) = match fields.as_slice() {
+            [h, m, s] => (*h, *m, *s, None),
+            [h, m, s, f] => (*h, *m, *s, Some(f.split('.').next()?)),
+            _ => return None,
+        };
+        let (seconds, fraction_denominator) = parse_decimal(seconds)?;
+        let whole = hours
+            .parse::<u128>()
+            .ok()?
+            .checked_mul(3_600)?
+            .checked_add(minutes.parse::<u128>().ok()?.checked_mul(60)?)?;
+        let (frame_numerator, frame_denominator) = time_base.frame_rate;
+        let frames = frames.map_or(Some(0), |f| f.parse::<u128>().ok())?;
+
+        // whole + seconds / fraction_denominator + frames / frame_rate
+        let denominator = fraction_denominator.checked_mul(frame_numerator)?;
+        let numerator = whole
+            .checked_mul(denominator)?
+            .checked_add(seconds.checked_mul(frame_numerator)?)?
+            .checked_add(
+                frames
+                    .checked_mul(frame_denominator)?
+                    .checked_mul(fraction_denominator)?,
+            )?;
+        return Some((numerator, denominator));
+    }
+
+    let split = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, metric) = value.split_at(split);
+    let (numerator, denominator) = parse_decimal(number)?;
+    let (frame_numerator, frame_denominator) = time_base.frame_rate;
+
+    match metric {
+        "h" => Some((numerator.checked_mul(3_600)?, denominator)),
+        "m" => Some((numerator.checked_mul(60)?, denominator)),
+        "s" => Some((numerator, denominator)),
+        "ms" => Some((numerator, denominator.checked_mul(1_000)?)),
+        "f" => Some((
+            numerator.checked_mul(frame_denominator)?,
+            denominator.checked_mul(frame_numerator)?,
+        )),
+        "t" => Some((
+            numerator,
+            denominator.checked_mul(time_base.ticks_per_second)?,
+        )),
+        _ => None,
+    }
+}
+
+/// `12.846` as `(12846, 1000)`.
+fn parse_decimal(raw: &str) -> Option<(u128, u128)> {
+    let (whole, fraction) = raw.split_once('.').unwrap_or((raw, ""));
+    if whole.is_empty() || fraction.len() > 9 {
+        return None;
+    }
+    let denominator = 10u128.pow(fraction.len() as u32);
+    let fraction = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u128>().ok()?
+    };
+    Some((
+        whole
+            .parse::<u128>()
+            .ok()?
+            .checked_mul(denominator)?
+            .checked_add(fraction)?,
+        denominator,
+    ))
+}
+
+/// Seconds as a fraction, to the nearest millisecond, or `None` on overflow.
+fn to_millis((numerator, denominator): (u128, u128)) -> Option<u128> {
+    Some(numerator.checked_mul(1_000)?.checked_add(denominator / 2)? / denominator)
+}
+
+fn read_cue(paragraph: Node, position: u32, context: &Context) -> Result<TtmlCue, TtmlError> {
+    let line_number = line_number(paragraph, context);
+    let time = |element: Node, name: &str| -> Result<Option<u128>, TtmlError> {
+        attribute(element, name)
+            .map(|value| {
+                parse_time(value, context.time_base)
+                    .and_then(to_millis)
+                    .ok_or_else(|| TtmlError::InvalidTime {
+                        line_number,
+                        value: value.to_string(),
+                    })
+            })
+            .transpose()
+    };
+
+    // Times on a `<p>` count from the begin of the `<div>`s and `<body>` it
+    // sits in.
+    let overflow = || TtmlError::Timestamp {
+        line_number,
+        error: TimestampError::Overflow,
+    };
+    let mut offset: u128 = 0;
+    for ancestor in paragraph.ancestors().skip(1) {
+        offset = offset
+            .checked_add(time(ancestor, "begin")?.unwrap_or(0))
+            .ok_or_else(overflow)?;
+    }
+    let begin = time(paragraph, "begin")?.ok_or(TtmlError::MissingTiming { line_number })?;
+    let end = match (time(paragraph, "end")?, time(paragraph, "dur")?) {
+        (Some(end), _) => end,
+        (None, Some(duration)) => begin.checked_add(duration).ok_or_else(overflow)?,
+        (None, None) => return Err(TtmlError::MissingTiming { line_number }),
+    };
+    if begin > end {
+        return Err(TtmlError::ReversedTiming { line_number });
+    }
+    let timestamp = |millis: u128| {
+        offset
+            .checked_add(millis)
+            .and_then(|millis| u64::try_from(millis).ok())
+            .ok_or(TimestampError::Overflow)
+            .and_then(Timestamp::from_millis)
+            .map_err(|error| TtmlError::Timestamp { line_number, error })
+    };
+
+    let mut lines = vec![Vec::new()];
+    collect_segments(paragraph, false, context, &mut lines);
+    for line in &mut lines {
+        trim_line(line);
+    }
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    Ok(TtmlCue {
+        id: attribute(paragraph, "id").map(str::to_string),
+        position: position_of(paragraph, context),
+        unit: SubtitleUnit {
+            index: SrtIndex::from(position),
+            timing: Timing {
+                start: timestamp(begin)?,
+                end: timestamp(end)?,
+            },
+            lines: lines
+                .iter()
+                .map(|line| line.iter().map(TtmlSegment::base_text).collect())
+                .collect(),
+        },
+        lines,
+    })
+}
+
+fn position_of(paragraph: Node, context: &Context) -> TtmlPosition {
+    let region_id = paragraph
+        .ancestors()
+        .find_map(|node| attribute(node, "region"));
+    let region = region_id.and_then(|id| context.regions.get(id)).copied();
+    // A property set on the cue itself wins over its region’s.
+    let property = |name: &str| {
+        styled(paragraph, name, context)
+            .or_else(|| region.and_then(|region| styled(region, name, context)))
+            .map(str::to_string)
+    };
+
+    let writing_mode = match property("writingMode").as_deref() {
+        Some("tbrl" | "tb") => WritingMode::VerticalRightToLeft,
+        Some("tblr") => WritingMode::VerticalLeftToRight,
+        _ => WritingMode::Horizontal,
+    };
+
+    TtmlPosition {
+        region: region_id.map(str::to_string),
+        origin: property("origin"),
+        extent: property("extent"),
+        writing_mode,
+        text_align: property("textAlign"),
+        display_align: property("displayAlign"),
+    }
+}
+
+/// Appends the content of `node` to `lines`, starting a new line at each
+/// `<br/>`.
+fn collect_segments(
+    node: Node,
+    combine_upright: bool,
+    context: &Context,
+    lines: &mut Vec<Vec<TtmlSegment>>,
+) {
+    for child in node.children() {
+        if child.is_text() {
+            let text = collapse_whitespace(child.text().unwrap_or_default());
+            push_text(lines, text, combine_upright);
+            continue;
+        } else if !child.is_element() {
+            continue;
+        }
+
+        match child.tag_name().name() {
+            "br" => lines.push(Vec::new()),
+            "span" if styled(child, "ruby", context) == Some("container") => {
+                let line = lines.last_mut().expect("There is always a current line");
+                line.extend(ruby_segments(child, context));
+            }
+            "span" => {
+                let combine = combine_upright
+                    || styled(child, "textCombine", context).is_some_and(|v| v != "none");
+                collect_segments(child, combine, context, lines);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Pairs the bases and texts of a ruby container in order. Both may sit in
+/// `baseContainer` and `textContainer` spans.
+fn ruby_segments(container: Node, context: &Context) -> Vec<TtmlSegment> {
+    let mut bases = Vec::new();
+    let mut texts = Vec::new();
+    for node in container.descendants().filter(|node| node.is_element()) {
+        match styled(node, "ruby", context) {
+            Some("base") => bases.push(node),
+            Some("text") => texts.push(node),
+            _ => {}
+        }
+    }
+
+    let text_content = |node: Node| -> String {
+        let raw: String = node
+            .descendants()
+            .filter(|n| n.is_text())
+            .filter_map(|n| n.text())
+            .collect();
+        collapse_whitespace(&raw).trim().to_string()
+    };
+    let position = texts
+        .first()
+        .and_then(|text| styled(*text, "rubyPosition", context))
+        .or_else(|| styled(container, "rubyPosition", context))
+        .map(str::to_string);
+
+    bases
+        .iter()
+        .zip(&texts)
+        .map(|(base, text)| TtmlSegment::Ruby {
+            base: text_content(*base),
+            text: text_content(*text),
+            position: position.clone(),
+        })
+        .collect()
+}
+
+fn push_text(lines: &mut [Vec<TtmlSegment>], text: String, combine_upright: bool) {
+    if text.is_empty() {
+        return;
+    }
+    let line = lines.last_mut().expect("There is always a current line");
+    match line.last_mut() {
+        Some(TtmlSegment::Text {
+            text: previous,
+            combine_upright: previous_combine,
+        }) if *previous_combine == combine_upright => previous.push_str(&text),
+        _ => line.push(TtmlSegment::Text {
+            text,
+            combine_upright,
+        }),
+    }
+}
+
+/// Turns each run of XML whitespace (as left by indentation) into one space.
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if matches!(c, ' ' | '\t' | '\n' | '\r') {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+/// Removes spaces at the start and end of a line, dropping segments that end
+/// up empty.
+fn trim_line(line: &mut Vec<TtmlSegment>) {
+    if let Some(TtmlSegment::Text { text, .. }) = line.first_mut() {
+        *text = text.trim_start().to_string();
+    }
+    if let Some(TtmlSegment::Text { text, .. }) = line.last_mut() {
+        *text = text.trim_end().to_string();
+    }
+    line.retain(|segment| !matches!(segment, TtmlSegment::Text { text, .. } if text.is_empty()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:tts="http://www.w3.org/ns/ttml#styling"
+    xmlns:ttp="http://www.w3.org/ns/ttml#parameter" ttp:tickRate="10000000"
+    ttp:frameRate="24" ttp:frameRateMultiplier="1000 1001" xml:lang="ja">
+  <head>
+    <styling>
+      <style xml:id="ruby_container" tts:ruby="container" tts:rubyPosition="outside"/>
+      <style xml:id="ruby_base" tts:ruby="base"/>
+      <style xml:id="ruby_text" tts:ruby="text"/>
+      <style xml:id="upright" tts:textCombine="all"/>
+    </styling>
+    <layout>
+      <region xml:id="bottom" tts:origin="10% 80%" tts:extent="80% 10%" tts:displayAlign="after"/>
+      <region xml:id="right" tts:origin="80% 10%" tts:extent="10% 80%" tts:writingMode="tbrl"/>
+    </layout>
+  </head>
+  <body>
+    <div>
+      <p xml:id="subtitle1" begin="128460000t" end="245630000t" region="bottom">
+        <span style="ruby_container"><span style="ruby_base">狡噛</span><span style="ruby_text">こうがみ</span></span>です<br/>
+        よろしく
+      </p>
+      <p begin="00:01:00:12" dur="2.5s" region="right"><span style="upright">12</span>月</p>
+    </div>
+  </body>
+</tt>"#;
+
+    #[test]
+    fn test_read_ttml() {
+        let document = read_ttml_str(INPUT).unwrap();
+        assert_eq!(document.cues.len(), 2);
+
+        let first = &document.cues[0];
+        assert_eq!(first.id.as_deref(), Some("subtitle1"));
+        assert_eq!(
+            first.unit.timing.to_string(),
+            "00:00:12,846 --> 00:00:24,563"
+        );
+        assert_eq!(
+            first.lines[0][0],
+            TtmlSegment::Ruby {
+                base: "狡噛".to_string(),
+                text: "こうがみ".to_string(),
+                position: Some("outside".to_string()),
+            }
+        );
+        assert_eq!(first.unit.lines, vec!["狡噛です", "よろしく"]);
+        assert_eq!(first.position.origin.as_deref(), Some("10% 80%"));
+        assert_eq!(first.position.display_align.as_deref(), Some("after"));
+
+        let second = &document.cues[1];
+        // 12 frames at 23.976 fps is 0.5005 s.
+        assert_eq!(
+            second.unit.timing.to_string(),
+            "00:01:00,501 --> 00:01:03,001"
+        );
+        assert_eq!(
+            second.position.writing_mode,
+            WritingMode::VerticalRightToLeft
+        );
+        assert!(matches!(
+            &second.lines[0][0],
+            TtmlSegment::Text { text, combine_upright: true } if text == "12"
+        ));
+        assert_eq!(document.units()[1].lines, vec!["12月"]);
+    }
+
+    #[test]
+    fn test_read_ttml_errors() {
+        let missing_end = r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><div>
+            <p begin="1s">Hi</p></div></body></tt>"#;
+        assert!(matches!(
+            read_ttml_str(missing_end),
+            Err(TtmlError::MissingTiming { line_number: 2 })
+        ));
+        assert!(matches!(read_ttml_str("<tt><p>"), Err(TtmlError::Xml(_))));
+
+        let reversed = r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><div>
+            <p begin="5s" end="1s">Hi</p></div></body></tt>"#;
+        assert!(matches!(
+            read_ttml_str(reversed),
+            Err(TtmlError::ReversedTiming { line_number: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_huge_times_are_errors() {
+        let huge_begin = r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><div>
+            <p begin="340282366920938463463374607431768211455s" end="2s">Hi</p></div></body></tt>"#;
+        assert!(matches!(
+            read_ttml_str(huge_begin),
+            Err(TtmlError::InvalidTime { line_number: 2, .. })
+        ));
+
+        let huge_duration = r#"<tt xmlns="http://www.w3.org/ns/ttml"><body><div>
+            <p begin="1s" dur="340282366920938463463374607431768211s">Hi</p></div></body></tt>"#;
+        assert!(matches!(
+            read_ttml_str(huge_duration),
+            Err(TtmlError::Timestamp {
+                line_number: 2,
+                error: TimestampError::Overflow,
+            })
+        ));
+    }
+}
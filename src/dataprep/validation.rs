@@ -0,0 +1,439 @@
+use crate::dataprep::diagnostics::SourceLocation;
+use crate::dataprep::ingestion::{
+    DetectedEncoding, ENCODING_SNIFF_WINDOW, PathError, SafeFilePath, SubtitleEncoding,
+    decode_subtitle_bytes, detect_encoding,
+};
+use crate::dataprep::parser::{ParseMode, Repair, SubtitleParser, SubtitleParserError};
+use crate::types::subtitle_unit::SubtitleUnit;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Bytes that aren’t valid UTF-8.
+    InvalidEncoding,
+    /// The file isn’t UTF-8 and was transcoded before checking.
+    LegacyEncoding,
+    /// A block that couldn’t be read as a unit at all.
+    MalformedUnit,
+    /// A block that starts with its timing line.
+    MissingIndex,
+    /// The first index isn’t `1`.
+    FirstIndexNotOne,
+    /// An index more than `1` higher than the one before it.
+    IndexGap,
+    /// An index equal to or lower than the one before it.
+    NonMonotonicIndex,
+    /// A timing whose start is later than its end.
+    StartAfterEnd,
+    /// A unit that starts before the previous unit ends.
+    Overlap,
+    /// A unit with no text, or only whitespace.
+    EmptyText,
+    /// A unit that was split in two by a blank line.
+    StrayBlankLine,
+    /// A unit whose start and end are the same.
+    ZeroDuration,
+}
+
+/// One problem found by [`validate_bytes`]. `location` points at the start of
+/// the offending line (or, for encoding problems, at the first bad byte).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub location: SourceLocation,
+    pub unit_index: Option<u32>,
+    pub message: String,
+}
+
+/// Every problem found in one SRT file, in the order they appear in the file.
+/// Serialises to JSON with serde, e.g. via [`ValidationReport::to_json`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub encoding: DetectedEncoding,
+    pub units_checked: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+pub fn validate_file(path: impl AsRef<Path>) -> Result<ValidationReport, SubtitleParserError> {
+    //! Reads a whole SRT file (after [`SafeFilePath`] validation) and checks it
+    //! with [`validate_bytes`].
+
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::try_from(raw_path)?;
+    let bytes = fs::read(checked_path)?;
+
+    Ok(validate_bytes(&bytes))
+}
+
+pub fn validate_bytes(bytes: &[u8]) -> ValidationReport {
+    //! Collects every problem in an SRT file instead of stopping at the first.
+    //!
+    //! Files detected as a legacy encoding (see [`detect_encoding`]) are
+    //! transcoded to UTF-8 first, with an info-level issue saying so; locations
+    //! then refer to the transcoded text.
+    //!
+    //! Invalid UTF-8 is reported once per affected line and then replaced with
+    //! `U+FFFD`, so the rest of the file can still be checked. The content is
+    //! parsed in [`ParseMode::Repair`]; each repair and each skipped block
+    //! becomes an issue, and the repaired units are then checked for index
+    //! steps, overlaps and empty text.
+
+    let encoding = detect_encoding(&bytes[..bytes.len().min(ENCODING_SNIFF_WINDOW)]);
+    if encoding.encoding != SubtitleEncoding::Utf8 {
+        let (decoded, _) = decode_subtitle_bytes(bytes, Some(encoding.encoding));
+        let mut report = validate_bytes(decoded.as_bytes());
+        report.encoding = encoding;
+        report.issues.insert(
+            0,
+            Issue {
+                severity: Severity::Info,
+                kind: IssueKind::LegacyEncoding,
+                location: SourceLocation {
+                    byte_offset: 0,
+                    line: 1,
+                    column: 1,
+                },
+                unit_index: None,
+                message: format!(
+                    "File is encoded as {:?} and was transcoded",
+                    encoding.encoding
+                ),
+            },
+        );
+        return report;
+    }
+
+    let line_starts = line_starts(bytes);
+    let locate = |line: usize| SourceLocation {
+        byte_offset: line_starts[line - 1],
+        line,
+        column: 1,
+    };
+
+    let mut issues = encoding_issues(bytes, &line_starts);
+
+    let content = String::from_utf8_lossy(bytes);
+    let mut parser = SubtitleParser::builder().mode(ParseMode::Repair).build();
+    let mut units = parser
+        .parse_str(&content)
+        .expect("Parsing from a string can’t fail to start");
+
+    let mut checker = UnitChecker::default();
+    let mut units_checked = 0;
+    while let Some(result) = units.next() {
+        // Repair mode skips instead of yielding errors, and reading from a string
+        // can’t fail with an IO error.
+        let Ok(unit) = result else { continue };
+        let line = units.last_unit_line().unwrap_or(1);
+        checker.check(&unit, locate(line), &mut issues);
+        units_checked += 1;
+    }
+
+    for skipped in units.skipped() {
+        // Offsets in the error context refer to the lossily decoded text, so only
+        // its line number is carried over.
+        let (location, unit_index) = match skipped.error.context() {
+            Some(context) => (locate(context.location.line), context.unit_index),
+            None => (locate(skipped.line_number), None),
+        };
+        issues.push(Issue {
+            severity: Severity::Error,
+            kind: IssueKind::MalformedUnit,
+            location,
+            unit_index,
+            message: skipped.error.to_string(),
+        });
+    }
+
+    for repair in units.repairs() {
+        let issue = match *repair {
+            Repair::MissingIndex {
+                line_number,
+                assigned,
+            } => Issue {
+                severity: Severity::Error,
+                kind: IssueKind::MissingIndex,
+                location: locate(line_number),
+                unit_index: Some(assigned),
+                message: format!("Block has no index; {} would follow on", assigned),
+            },
+            Repair::SwappedTimestamps {
+                line_number,
+                unit_index,
+            } => Issue {
+                severity: Severity::Error,
+                kind: IssueKind::StartAfterEnd,
+                location: locate(line_number),
+                unit_index: Some(unit_index),
+                message: "Start timestamp is later than end timestamp".to_string(),
+            },
+            Repair::ExtraBlankLines {
+                line_number,
+                merged_into,
+            } => Issue {
+                severity: Severity::Warning,
+                kind: IssueKind::StrayBlankLine,
+                location: locate(line_number),
+                unit_index: Some(merged_into),
+                message: format!("Blank line inside unit {}", merged_into),
+            },
+        };
+        issues.push(issue);
+    }
+
+    issues.sort_by_key(|issue| issue.location.byte_offset);
+
+    ValidationReport {
+        encoding,
+        units_checked,
+        issues,
+    }
+}
+
+/// Byte offset at which each line starts; the first line starts at 0. Line
+/// endings are counted the same way as in [`NormalisedLines`]: `\n`, `\r\n` or
+/// a lone `\r`.
+///
+/// [`NormalisedLines`]: crate::dataprep::normalisation::NormalisedLines
+fn line_starts(bytes: &[u8]) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (i, &byte) in bytes.iter().enumerate() {
+        let ends_line = match byte {
+            b'\n' => true,
+            b'\r' => bytes.get(i + 1) != Some(&b'\n'),
+            _ => false,
+        };
+        if ends_line {
+            starts.push(i + 1);
+        }
+    }
+    starts
+}
+
+/// One issue for every line containing invalid UTF-8, pointing at the first bad
+/// byte on that line.
+fn encoding_issues(bytes: &[u8], line_starts: &[usize]) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+    let mut offset = 0;
+
+    for chunk in bytes.utf8_chunks() {
+        offset += chunk.valid().len();
+        let invalid_len = chunk.invalid().len();
+
+        if invalid_len > 0 {
+            let line = line_starts.partition_point(|&start| start <= offset);
+            let already_reported = issues
+                .last()
+                .is_some_and(|issue| issue.location.line == line);
+            if !already_reported {
+                let line_start = line_starts[line - 1];
+                let column = String::from_utf8_lossy(&bytes[line_start..offset])
+                    .chars()
+                    .count()
+                    + 1;
+                issues.push(Issue {
+                    severity: Severity::Error,
+                    kind: IssueKind::InvalidEncoding,
+                    location: SourceLocation {
+                        byte_offset: offset,
+                        line,
+                        column,
+                    },
+                    unit_index: None,
+                    message: format!("Invalid UTF-8 byte sequence {:02X?}", chunk.invalid()),
+                });
+            }
+        }
+
+        offset += invalid_len;
+    }
+
+    issues
+}
+
+/// Checks that need the unit before, carried from one unit to the next.
+#[derive(Debug, Default)]
+struct UnitChecker {
+    previous: Option<SubtitleUnit>,
+}
+
+impl UnitChecker {
+    fn check(&mut self, unit: &SubtitleUnit, location: SourceLocation, issues: &mut Vec<Issue>) {
+        let index = unit.index.value();
+        let mut push = |severity: Severity, kind: IssueKind, message: String| {
+            issues.push(Issue {
+                severity,
+                kind,
+                location,
+                unit_index: Some(index),
+                message,
+            })
+        };
+
+        match &self.previous {
+            None if index != 1 => push(
+                Severity::Warning,
+                IssueKind::FirstIndexNotOne,
+                format!("First index is {} instead of 1", index),
+            ),
+            None => {}
+            Some(previous) => {
+                let previous_index = previous.index.value();
+                if index <= previous_index {
+                    push(
+                        Severity::Error,
+                        IssueKind::NonMonotonicIndex,
+                        format!("Index {} follows index {}", index, previous_index),
+                    );
+                } else if index > previous_index + 1 {
+                    push(
+                        Severity::Error,
+                        IssueKind::IndexGap,
+                        format!(
+                            "Indices {} to {} are missing",
+                            previous_index + 1,
+                            index - 1
+                        ),
+                    );
+                }
+
+                if unit.timing.start < previous.timing.end {
+                    push(
+                        Severity::Warning,
+                        IssueKind::Overlap,
+                        format!("Unit starts before unit {} ends", previous_index),
+                    );
+                }
+            }
+        }
+
+        if unit.lines.iter().all(|line| line.trim().is_empty()) {
+            push(
+                Severity::Warning,
+                IssueKind::EmptyText,
+                "Unit has no text".to_string(),
+            );
+        }
+        if unit.timing.start == unit.timing.end {
+            push(
+                Severity::Info,
+                IssueKind::ZeroDuration,
+                "Unit starts and ends at the same time".to_string(),
+            );
+        }
+
+        self.previous = Some(unit.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_clean_episode() {
+        let report = validate_file("data/raw/psycho-pass-s01e01-jp.srt").unwrap();
+
+        assert_eq!(report.units_checked, 305);
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn test_validate_collects_every_issue() {
+        let input =
+            b"2\n00:00:01,000 --> 00:00:03,000\nHello\n\n4\n00:00:02,000 --> 00:00:02,000\n\n\n\
+4\n00:00:05,000 --> 00:00:04,000\nW\xFFrld\n";
+        let report = validate_bytes(input);
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                IssueKind::FirstIndexNotOne,
+                IssueKind::IndexGap,
+                IssueKind::Overlap,
+                IssueKind::EmptyText,
+                IssueKind::ZeroDuration,
+                IssueKind::NonMonotonicIndex,
+                IssueKind::StartAfterEnd,
+                IssueKind::InvalidEncoding,
+            ]
+        );
+        assert_eq!(report.issues[7].location.line, 11);
+        assert_eq!(report.issues[7].location.column, 2);
+        assert!(
+            report
+                .to_json()
+                .unwrap()
+                .contains("\"severity\": \"warning\"")
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_malformed_block_after_unit() {
+        let report = validate_bytes(
+            b"1\n00:00:01,000 --> 00:00:02,000\nA\n\n2\n00:00:03,000 -> 00:00:04,000\nB\n",
+        );
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+
+        assert_eq!(kinds, vec![IssueKind::MalformedUnit]);
+        assert_eq!(report.issues[0].severity, Severity::Error);
+        assert_eq!(report.issues[0].location.line, 6);
+    }
+
+    #[test]
+    fn test_swapped_timestamps_point_at_timing_line() {
+        // The second block has no index, so its timing line is its first line.
+        let report = validate_bytes(
+            b"1\n00:00:01,000 --> 00:00:02,000\nA\n\n00:00:05,000 --> 00:00:04,000\nB\n",
+        );
+        let issue = report
+            .issues
+            .iter()
+            .find(|issue| issue.kind == IssueKind::StartAfterEnd)
+            .unwrap();
+
+        assert_eq!(issue.location.line, 5);
+        assert_eq!(issue.unit_index, Some(2));
+    }
+
+    #[test]
+    fn test_validate_reports_legacy_encoding() {
+        let (bytes, _, _) =
+            encoding_rs::SHIFT_JIS.encode("1\n00:00:01,000 --> 00:00:02,000\n足音\n");
+        let report = validate_bytes(&bytes);
+
+        assert_eq!(report.encoding.encoding, SubtitleEncoding::ShiftJis);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::LegacyEncoding);
+    }
+}
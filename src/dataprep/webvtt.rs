@@ -0,0 +1,695 @@
+use crate::dataprep::ingestion::{PathError, SafeFilePath};
+use crate::dataprep::normalisation::{LineEnding, NormalisedLine, NormalisedLines};
+use crate::types::srt_index::SrtIndex;
+use crate::types::subtitle_unit::SubtitleUnit;
+use crate::types::timestamp::{Timestamp, TimestampError};
+use crate::types::timing::{Timing, TimingError};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Write};
+use std::path::Path;
+
+const FILE_SIGNATURE: &str = "WEBVTT";
+const TIMING_SEPARATOR: &str = "-->";
+
+/// A parsed WebVTT file. Blocks are kept in source order so NOTE and STYLE
+/// blocks land back where they were when the document is written out again.
+///
+/// ```text
+/// WEBVTT - Episode 1
+///
+/// NOTE Timed against the broadcast master
+///
+/// intro
+/// 00:12.846 --> 00:24.563 align:start line:0
+/// 犯罪係数
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebVttDocument {
+    /// Text following `WEBVTT` on the first line, e.g. `- Episode 1`.
+    pub title: Option<String>,
+    /// Any further lines of the header block (old-style metadata headers).
+    pub header_lines: Vec<String>,
+    pub blocks: Vec<WebVttBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebVttBlock {
+    Cue(WebVttCue),
+    /// The lines of a `NOTE` block, including the `NOTE` line itself.
+    Note(Vec<String>),
+    /// The CSS of a `STYLE` block, without the `STYLE` line.
+    Style(Vec<String>),
+    /// The settings of a `REGION` block, without the `REGION` line.
+    Region(Vec<String>),
+}
+
+/// One cue: a [`SubtitleUnit`] plus what WebVTT has on top of SRT. The unit’s
+/// index is the cue’s 1-based position in the file, since WebVTT cues have no
+/// numbering of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebVttCue {
+    pub identifier: Option<String>,
+    pub settings: CueSettings,
+    pub unit: SubtitleUnit,
+}
+
+/// The settings after the end timestamp of a cue timing line. Values other
+/// than `vertical` and `align` are kept as written (`line:-1`, `line:10%,end`,
+/// `position:50%,line-left`), since they mix numbers, percentages and anchors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueSettings {
+    pub vertical: Option<Vertical>,
+    pub line: Option<String>,
+    pub position: Option<String>,
+    pub size: Option<String>,
+    pub align: Option<Align>,
+    pub region: Option<String>,
+}
+
+/// Vertical text direction, as used for Japanese subtitles set down the side
+/// of the picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vertical {
+    /// `rl`: lines grow from right to left.
+    RightToLeft,
+    /// `lr`: lines grow from left to right.
+    LeftToRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Left,
+    Right,
+}
+
+#[derive(Debug)]
+pub enum WebVttError {
+    Io(io::Error),
+    Path(PathError),
+    /// The first line isn’t `WEBVTT`, optionally followed by a space or tab and
+    /// more text.
+    MissingSignature,
+    /// A block that isn’t a NOTE, STYLE or REGION block has no timing line.
+    MissingTiming {
+        line_number: usize,
+    },
+    Timing {
+        line_number: usize,
+        error: TimingError,
+    },
+    /// A cue setting has an unknown name or a value it can’t take.
+    InvalidSetting {
+        line_number: usize,
+        setting: String,
+    },
+}
+
+impl Display for WebVttError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebVttError::Io(e) => write!(f, "I/O error: {}", e),
+            WebVttError::Path(e) => write!(f, "Path error: {}", e),
+            WebVttError::MissingSignature => {
+                write!(f, "File doesn’t start with the WEBVTT signature")
+            }
+            WebVttError::MissingTiming { line_number } => {
+                write!(f, "Line {}: cue has no timing line", line_number)
+            }
+            WebVttError::Timing { line_number, error } => {
+                write!(f, "Line {}: invalid cue timing: {:?}", line_number, error)
+            }
+            WebVttError::InvalidSetting {
+                line_number,
+                setting,
+            } => write!(f, "Line {}: invalid cue setting {}", line_number, setting),
+        }
+    }
+}
+
+impl Error for WebVttError {}
+
+impl From<io::Error> for WebVttError {
+    fn from(error: io::Error) -> Self {
+        WebVttError::Io(error)
+    }
+}
+
+impl From<PathError> for WebVttError {
+    fn from(error: PathError) -> Self {
+        WebVttError::Path(error)
+    }
+}
+
+impl WebVttDocument {
+    pub fn cues(&self) -> impl Iterator<Item = &WebVttCue> {
+        self.blocks.iter().filter_map(|block| match block {
+            WebVttBlock::Cue(cue) => Some(cue),
+            _ => None,
+        })
+    }
+
+    /// The cues as plain units, dropping identifiers and settings.
+    pub fn units(&self) -> Vec<SubtitleUnit> {
+        self.cues().map(|cue| cue.unit.clone()).collect()
+    }
+
+    /// Wraps SRT units as cues without identifiers or settings.
+    pub fn from_units(units: &[SubtitleUnit]) -> Self {
+        WebVttDocument {
+            blocks: units
+                .iter()
+                .map(|unit| {
+                    WebVttBlock::Cue(WebVttCue {
+                        identifier: None,
+                        settings: CueSettings::default(),
+                        unit: unit.clone(),
+                    })
+                })
+                .collect(),
+            ..WebVttDocument::default()
+        }
+    }
+}
+
+impl CueSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == CueSettings::default()
+    }
+
+    fn parse(raw: &str, line_number: usize) -> Result<Self, WebVttError> {
+        let mut settings = CueSettings::default();
+
+        for setting in raw.split_whitespace() {
+            let invalid = || WebVttError::InvalidSetting {
+                line_number,
+                setting: setting.to_string(),
+            };
+            let (name, value) = setting.split_once(':').ok_or_else(invalid)?;
+            if value.is_empty() {
+                return Err(invalid());
+            }
+
+            match name {
+                "vertical" => {
+                    settings.vertical = Some(match value {
+                        "rl" => Vertical::RightToLeft,
+                        "lr" => Vertical::LeftToRight,
+                        _ => return Err(invalid()),
+                    })
+                }
+                "align" => {
+                    settings.align = Some(match value {
+                        "start" => Align::Start,
+                        "center" | "middle" => Align::Center,
+                        "end" => Align::End,
+                        "left" => Align::Left,
+                        "right" => Align::Right,
+                        _ => return Err(invalid()),
+                    })
+                }
+                "line" => settings.line = Some(value.to_string()),
+                "position" => settings.position = Some(value.to_string()),
+                "size" => settings.size = Some(value.to_string()),
+                "region" => settings.region = Some(value.to_string()),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+impl Display for CueSettings {
+    /// Writes the settings space-separated, in a fixed order.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let vertical = self.vertical.map(|vertical| match vertical {
+            Vertical::RightToLeft => "rl",
+            Vertical::LeftToRight => "lr",
+        });
+        let align = self.align.map(|align| match align {
+            Align::Start => "start",
+            Align::Center => "center",
+            Align::End => "end",
+            Align::Left => "left",
+            Align::Right => "right",
+        });
+
+        let settings = [
+            ("vertical", vertical),
+            ("line", self.line.as_deref()),
+            ("position", self.position.as_deref()),
+            ("size", self.size.as_deref()),
+            ("align", align),
+            ("region", self.region.as_deref()),
+        ];
+        let mut separator = "";
+        for (name, value) in settings {
+            if let Some(value) = value {
+                write!(f, "{}{}:{}", separator, name, value)?;
+                separator = " ";
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn read_webvtt<R: BufRead>(reader: R) -> Result<WebVttDocument, WebVttError> {
+    //! Reads a whole WebVTT file. A byte-order mark and any mix of `\n`, `\r\n`
+    //! and `\r` line endings are accepted, as for SRT.
+    //!
+    //! Cue text is kept as written, including tags such as `<i>` or `<ruby>`.
+
+    let mut lines = NormalisedLines::new(reader);
+    let mut document = WebVttDocument::default();
+
+    let first_line = lines.read_line()?.ok_or(WebVttError::MissingSignature)?;
+    document.title = parse_signature(&first_line.text)?;
+    // The header ends at the first blank line, which may come straight away.
+    while let Some(line) = lines.read_line()? {
+        if line.text.is_empty() {
+            break;
+        }
+        document.header_lines.push(line.text);
+    }
+
+    let mut cue_count: u32 = 0;
+    loop {
+        let block = read_block(&mut lines)?;
+        let Some(first) = block.first() else {
+            break;
+        };
+
+        // A cue’s timing line is its first or second line (after an
+        // identifier); anything else is a NOTE, STYLE or REGION block.
+        let is_cue = block
+            .iter()
+            .take(2)
+            .any(|line| line.text.contains(TIMING_SEPARATOR));
+        let keyword = first.text.split([' ', '\t']).next().unwrap_or_default();
+        let contents = || block.iter().map(|line| line.text.clone());
+        let parsed = match keyword {
+            _ if is_cue => None,
+            "NOTE" => Some(WebVttBlock::Note(contents().collect())),
+            "STYLE" if first.text == "STYLE" => {
+                Some(WebVttBlock::Style(contents().skip(1).collect()))
+            }
+            "REGION" if first.text == "REGION" => {
+                Some(WebVttBlock::Region(contents().skip(1).collect()))
+            }
+            _ => None,
+        };
+
+        let parsed = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                cue_count += 1;
+                WebVttBlock::Cue(parse_cue(&block, cue_count)?)
+            }
+        };
+        document.blocks.push(parsed);
+    }
+
+    Ok(document)
+}
+
+pub fn read_webvtt_str(content: &str) -> Result<WebVttDocument, WebVttError> {
+    read_webvtt(Cursor::new(content))
+}
+
+pub fn read_webvtt_file(path: impl AsRef<Path>) -> Result<WebVttDocument, WebVttError> {
+    // SafeFilePath only validates UTF-8 paths, so anything else is rejected up front.
+    let raw_path = path.as_ref().to_str().ok_or(PathError::IllegalCharacters)?;
+    let checked_path = SafeFilePath::with_extensions(raw_path, &["vtt"])?;
+
+    read_webvtt(BufReader::new(File::open(checked_path)?))
+}
+
+/// Checks the `WEBVTT` line and returns any text after it.
+fn parse_signature(line: &str) -> Result<Option<String>, WebVttError> {
+    let rest = line
+        .strip_prefix(FILE_SIGNATURE)
+        .ok_or(WebVttError::MissingSignature)?;
+
+    match rest.chars().next() {
+        None => Ok(None),
+        Some(' ' | '\t') => Ok(Some(rest.trim().to_string()).filter(|title| !title.is_empty())),
+        Some(_) => Err(WebVttError::MissingSignature),
+    }
+}
+
+/// Skips blank lines, then collects lines up to the next blank line or the end
+/// of the source. Returns an empty block at the end of the source.
+fn read_block<R: BufRead>(
+    lines: &mut NormalisedLines<R>,
+) -> Result<Vec<NormalisedLine>, WebVttError> {
+    let mut block = Vec::new();
+
+    while let Some(line) = lines.read_line()? {
+        if line.text.is_empty() {
+            if block.is_empty() {
+                continue;
+            }
+            break;
+        }
+        block.push(line);
+    }
+
+    Ok(block)
+}
+
+fn parse_cue(block: &[NormalisedLine], position: u32) -> Result<WebVttCue, WebVttError> {
+    let (identifier, rest) = if block[0].text.contains(TIMING_SEPARATOR) {
+        (None, block)
+    } else {
+        (Some(block[0].text.clone()), &block[1..])
+    };
+
+    let timing_line = rest
+        .first()
+        .filter(|line| line.text.contains(TIMING_SEPARATOR))
+        .ok_or(WebVttError::MissingTiming {
+            line_number: block[0].line_number,
+        })?;
+    let line_number = timing_line.line_number;
+    let timing_error = |error: TimingError| WebVttError::Timing { line_number, error };
+
+    let (raw_start, after_separator) = timing_line
+        .text
+        .split_once(TIMING_SEPARATOR)
+        .expect("Timing line contains the separator");
+    let after_separator = after_separator.trim_start();
+    let (raw_end, raw_settings) = after_separator
+        .split_once([' ', '\t'])
+        .unwrap_or((after_separator, ""));
+
+    let start = parse_timestamp(raw_start.trim()).map_err(|e| timing_error(e.into()))?;
+    let end = parse_timestamp(raw_end).map_err(|e| timing_error(e.into()))?;
+    if start > end {
+        return Err(timing_error(TimingError::malformed(
+            "Start timestamp is later than end timestamp",
+            &timing_line.text,
+        )));
+    }
+
+    Ok(WebVttCue {
+        identifier,
+        settings: CueSettings::parse(raw_settings, line_number)?,
+        unit: SubtitleUnit {
+            index: SrtIndex::from(position),
+            timing: Timing { start, end },
+            lines: rest[1..].iter().map(|line| line.text.clone()).collect(),
+        },
+    })
+}
+
+/// Parses `mm:ss.ttt` or `hh:mm:ss.ttt`. Hours may have more than two digits;
+/// everything else has exactly two (three for milliseconds).
+fn parse_timestamp(s: &str) -> Result<Timestamp, TimestampError> {
+    if s.is_empty() {
+        return Err(TimestampError::EmptyString);
+    }
+
+    let malformed = |msg: &str| TimestampError::malformed(msg, s);
+    let (clock, millis) = s
+        .split_once('.')
+        .ok_or_else(|| malformed("WebVTT timestamps need a . before the milliseconds"))?;
+    let fields: Vec<&str> = clock.split(':').collect();
+    let (hours, minutes, seconds) = match fields.as_slice() {
+        [minutes, seconds] => ("0", *minutes, *seconds),
+        [hours, minutes, seconds] if hours.len() >= 2 => (*hours, *minutes, *seconds),
+        _ => return Err(malformed("Timestamp must be mm:ss.ttt or hh:mm:ss.ttt")),
+    };
+
+    let all_digits = |field: &str| field.chars().all(|c| c.is_ascii_digit());
+    if minutes.len() != 2 || seconds.len() != 2 || millis.len() != 3 {
+        return Err(malformed(
+            "Minutes and seconds need two digits and milliseconds three",
+        ));
+    } else if ![hours, minutes, seconds, millis]
+        .into_iter()
+        .all(all_digits)
+    {
+        return Err(malformed("Timestamp fields may only contain digits"));
+    }
+
+    // The SRT parser does the range checks (minutes and seconds up to 59,
+    // hours up to 255).
+    format!("{}:{}:{},{}", hours, minutes, seconds, millis).parse()
+}
+
+/// `hh:mm:ss.ttt`. Hours are always written, even when zero.
+fn format_timestamp(timestamp: Timestamp) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        timestamp.hours, timestamp.minutes, timestamp.seconds, timestamp.milliseconds
+    )
+}
+
+/// Writes WebVTT to any `io::Write`. The `WEBVTT` header is written before the
+/// first block, and every block is followed by a blank line.
+///
+/// ```text
+/// let mut writer = WebVttWriter::new(File::create("out.vtt")?);
+/// writer.write_units(&units)?;
+/// ```
+#[derive(Debug)]
+pub struct WebVttWriter<W: Write> {
+    inner: W,
+    line_ending: LineEnding,
+    header_written: bool,
+}
+
+impl<W: Write> WebVttWriter<W> {
+    /// Creates a writer using `\n` line endings.
+    pub fn new(inner: W) -> Self {
+        WebVttWriter {
+            inner,
+            line_ending: LineEnding::Lf,
+            header_written: false,
+        }
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Writes the title and header lines of `document`, then its blocks.
+    pub fn write_document(&mut self, document: &WebVttDocument) -> io::Result<()> {
+        self.write_header(document.title.as_deref(), &document.header_lines)?;
+        for block in &document.blocks {
+            self.write_block(block)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_block(&mut self, block: &WebVttBlock) -> io::Result<()> {
+        match block {
+            WebVttBlock::Cue(cue) => self.write_cue(cue),
+            WebVttBlock::Note(lines) => self.write_lines(None, lines),
+            WebVttBlock::Style(lines) => self.write_lines(Some("STYLE"), lines),
+            WebVttBlock::Region(lines) => self.write_lines(Some("REGION"), lines),
+        }
+    }
+
+    pub fn write_cue(&mut self, cue: &WebVttCue) -> io::Result<()> {
+        self.write_header(None, &[])?;
+        let ending = self.line_ending.as_str();
+
+        if let Some(identifier) = &cue.identifier {
+            write!(self.inner, "{}{}", identifier, ending)?;
+        }
+        let timing = cue.unit.timing;
+        write!(
+            self.inner,
+            "{} {} {}",
+            format_timestamp(timing.start),
+            TIMING_SEPARATOR,
+            format_timestamp(timing.end)
+        )?;
+        if !cue.settings.is_empty() {
+            write!(self.inner, " {}", cue.settings)?;
+        }
+        self.inner.write_all(ending.as_bytes())?;
+
+        self.write_lines(None, &cue.unit.lines)
+    }
+
+    /// Writes `unit` as a cue without identifier or settings.
+    pub fn write_unit(&mut self, unit: &SubtitleUnit) -> io::Result<()> {
+        self.write_cue(&WebVttCue {
+            identifier: None,
+            settings: CueSettings::default(),
+            unit: unit.clone(),
+        })
+    }
+
+    pub fn write_units<'a>(
+        &mut self,
+        units: impl IntoIterator<Item = &'a SubtitleUnit>,
+    ) -> io::Result<()> {
+        for unit in units {
+            self.write_unit(unit)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes the header block unless it has been written already.
+    fn write_header(&mut self, title: Option<&str>, header_lines: &[String]) -> io::Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+
+        match title {
+            Some(title) => write!(self.inner, "{} {}", FILE_SIGNATURE, title)?,
+            None => write!(self.inner, "{}", FILE_SIGNATURE)?,
+        }
+        self.inner.write_all(self.line_ending.as_str().as_bytes())?;
+        self.write_lines(None, header_lines)
+    }
+
+    /// Writes an optional keyword line and `lines`, then the blank line that
+    /// ends the block.
+    fn write_lines(&mut self, keyword: Option<&str>, lines: &[String]) -> io::Result<()> {
+        self.write_header(None, &[])?;
+        let ending = self.line_ending.as_str();
+
+        for line in keyword.into_iter().chain(lines.iter().map(String::as_str)) {
+            write!(self.inner, "{}{}", line, ending)?;
+        }
+        self.inner.write_all(ending.as_bytes())
+    }
+}
+
+pub fn to_webvtt_string(units: &[SubtitleUnit]) -> String {
+    //! Writes `units` as WebVTT with `\n` line endings.
+
+    let mut writer = WebVttWriter::new(Vec::new());
+    writer
+        .write_units(units)
+        .expect("Writing to a Vec can’t fail");
+
+    String::from_utf8(writer.into_inner()).expect("WebVTT output is always UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    const INPUT: &str = "\u{FEFF}WEBVTT - Episode 1\r
+\r
+STYLE\r
+::cue { color: yellow }\r
+\r
+NOTE Timed against\r
+the broadcast master\r
+\r
+intro\r
+00:12.846 --> 00:24.563 align:start line:0 vertical:rl\r
+犯罪係数\r
+<i>測定中</i>\r
+\r
+01:02:03.004 --> 01:02:05.000\r
+シビュラ\r
+";
+
+    #[test]
+    fn test_read_webvtt() {
+        let document = read_webvtt_str(INPUT).unwrap();
+        assert_eq!(document.title.as_deref(), Some("- Episode 1"));
+        assert!(matches!(&document.blocks[0], WebVttBlock::Style(css) if css.len() == 1));
+        assert!(matches!(&document.blocks[1], WebVttBlock::Note(note) if note.len() == 2));
+
+        let cues: Vec<&WebVttCue> = document.cues().collect();
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].identifier.as_deref(), Some("intro"));
+        assert_eq!(cues[0].settings.align, Some(Align::Start));
+        assert_eq!(cues[0].settings.vertical, Some(Vertical::RightToLeft));
+        assert_eq!(cues[0].settings.line.as_deref(), Some("0"));
+        assert_eq!(
+            cues[0].unit.timing.to_string(),
+            "00:00:12,846 --> 00:00:24,563"
+        );
+        assert_eq!(cues[0].unit.lines, vec!["犯罪係数", "<i>測定中</i>"]);
+        assert_eq!(cues[1].unit.index.value(), 2);
+        assert_eq!(
+            cues[1].unit.timing.to_string(),
+            "01:02:03,004 --> 01:02:05,000"
+        );
+    }
+
+    #[test]
+    fn test_write_document_round_trips() {
+        let document = read_webvtt_str(INPUT).unwrap();
+        let mut writer = WebVttWriter::new(Vec::new());
+        writer.write_document(&document).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert!(
+            written
+                .contains("intro\n00:00:12.846 --> 00:00:24.563 vertical:rl line:0 align:start\n")
+        );
+        assert_eq!(read_webvtt_str(&written).unwrap(), document);
+    }
+
+    #[test]
+    fn test_srt_episode_converts_to_webvtt() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        let vtt = to_webvtt_string(&units);
+
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:12.846 --> "));
+        assert_eq!(read_webvtt_str(&vtt).unwrap().units(), units);
+    }
+
+    #[test]
+    fn test_read_webvtt_errors() {
+        // `.vtt` gets past the extension check; the file just doesn’t exist.
+        assert!(matches!(
+            read_webvtt_file("data/raw/episode.vtt"),
+            Err(WebVttError::Path(PathError::FileNotFound))
+        ));
+        assert!(matches!(
+            read_webvtt_file("data/raw/psycho-pass-s01e01-jp.srt"),
+            Err(WebVttError::Path(PathError::IncorrectExtension {
+                expected: ["vtt"]
+            }))
+        ));
+        assert!(matches!(
+            read_webvtt_str("WEBVTT\n\n99999999999999999999999:00:01.000 --> 00:00:02.000\nA\n"),
+            Err(WebVttError::Timing { line_number: 3, .. })
+        ));
+        assert!(matches!(
+            read_webvtt_str("WEBVTTX\n"),
+            Err(WebVttError::MissingSignature)
+        ));
+        assert!(matches!(
+            read_webvtt_str("WEBVTT\n\n00:01,000 --> 00:02.000\nHi\n"),
+            Err(WebVttError::Timing { line_number: 3, .. })
+        ));
+        assert!(matches!(
+            read_webvtt_str("WEBVTT\n\n00:01.000 --> 00:02.000 colour:red\nHi\n"),
+            Err(WebVttError::InvalidSetting { line_number: 3, .. })
+        ));
+        assert!(matches!(
+            read_webvtt_str("WEBVTT\n\nintro\nHi\n"),
+            Err(WebVttError::MissingTiming { line_number: 3 })
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::dataprep::normalisation::LineEnding;
+use crate::types::subtitle_unit::SubtitleUnit;
+use std::io::{self, Write};
+
+const BYTE_ORDER_MARK: &str = "\u{FEFF}";
+
+/// Writes [`SubtitleUnit`]s as SRT to any `io::Write`.
+///
+/// Every block, including the last, is followed by one blank line. Parsing a
+/// well-formed file and writing its units back out with the same line ending
+/// and byte-order mark gives the original bytes;
+/// [`NormalisationReport`](crate::dataprep::normalisation::NormalisationReport)
+/// says which ones the source used.
+///
+/// ```text
+/// let mut writer = SrtWriter::new(File::create("out.srt")?).line_ending(LineEnding::CrLf);
+/// writer.write_units(&units)?;
+/// ```
+#[derive(Debug)]
+pub struct SrtWriter<W: Write> {
+    inner: W,
+    line_ending: LineEnding,
+    byte_order_mark: bool,
+    units_written: usize,
+}
+
+impl<W: Write> SrtWriter<W> {
+    /// Creates a writer using `\n` line endings and no byte-order mark.
+    pub fn new(inner: W) -> Self {
+        SrtWriter {
+            inner,
+            line_ending: LineEnding::Lf,
+            byte_order_mark: false,
+            units_written: 0,
+        }
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Whether to start the output with a UTF-8 byte-order mark.
+    pub fn byte_order_mark(mut self, byte_order_mark: bool) -> Self {
+        self.byte_order_mark = byte_order_mark;
+        self
+    }
+
+    pub fn write_unit(&mut self, unit: &SubtitleUnit) -> io::Result<()> {
+        let ending = self.line_ending.as_str();
+
+        if self.units_written == 0 && self.byte_order_mark {
+            self.inner.write_all(BYTE_ORDER_MARK.as_bytes())?;
+        }
+
+        write!(
+            self.inner,
+            "{}{}{}{}",
+            unit.index, ending, unit.timing, ending
+        )?;
+        for line in &unit.lines {
+            write!(self.inner, "{}{}", line, ending)?;
+        }
+        self.inner.write_all(ending.as_bytes())?;
+
+        self.units_written += 1;
+        Ok(())
+    }
+
+    pub fn write_units<'a>(
+        &mut self,
+        units: impl IntoIterator<Item = &'a SubtitleUnit>,
+    ) -> io::Result<()> {
+        for unit in units {
+            self.write_unit(unit)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+pub fn to_srt_string(units: &[SubtitleUnit]) -> String {
+    //! Writes `units` as SRT with `\n` line endings and no byte-order mark.
+
+    let mut writer = SrtWriter::new(Vec::new());
+    writer
+        .write_units(units)
+        .expect("Writing to a Vec can’t fail");
+
+    // Everything written is either a `&str` or `Display` output, so it is UTF-8.
+    String::from_utf8(writer.into_inner()).expect("SRT output is always UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+    use std::fs;
+
+    #[test]
+    fn test_round_trip_is_byte_identical() {
+        let path = "data/raw/psycho-pass-s01e01-jp.srt";
+        let original = fs::read(path).unwrap();
+
+        let mut units = SubtitleParser::new().parse_file(path).unwrap();
+        let parsed: Vec<SubtitleUnit> = units.by_ref().collect::<Result<_, _>>().unwrap();
+        let report = units.normalisations();
+
+        let mut writer = SrtWriter::new(Vec::new())
+            .line_ending(report.dominant_line_ending())
+            .byte_order_mark(report.byte_order_mark);
+        writer.write_units(&parsed).unwrap();
+
+        assert_eq!(writer.into_inner(), original);
+    }
+
+    #[test]
+    fn test_to_srt_string() {
+        let input = "1\n00:00:01,000 --> 00:00:02,500\nHello\nWorld\n\n2\n00:01:00,000 --> 00:01:01,000\n♪～\n\n";
+        let units = SubtitleParser::new().read_str(input).unwrap();
+
+        assert_eq!(to_srt_string(&units), input);
+        assert_eq!(
+            units[0].to_string(),
+            "1\n00:00:01,000 --> 00:00:02,500\nHello\nWorld"
+        );
+    }
+}
@@ -2,10 +2,12 @@
 #![allow(unused_imports)]
 #![allow(unused_variables)]
 
+mod cli;
 mod dataprep;
 mod types;
 
 use dataprep::cleaning::{clean_subtitles, helper_dedupe_and_sort};
+use dataprep::diagnostics::render_diagnostic;
 use dataprep::parser::SubtitleParser;
 use serde::Deserialize;
 use std::collections::{BTreeSet, HashMap};
@@ -15,7 +17,7 @@ use std::str::FromStr;
 use std::{env, fs};
 
 use crate::dataprep::ingestion::SafeFilePath;
-use crate::dataprep::subtitle_set_builder::SubtitleUnit;
+use crate::types::subtitle_unit::SubtitleUnit;
 use crate::types::srt_index::SrtIndex;
 use crate::types::timing::Timing;
 
@@ -28,44 +30,47 @@ use crate::types::timing::Timing;
 ///     index: int32,
 ///     raw_content: &str
 /// }
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // We need this SafeFilePath -> File -> BufReader chain because:
-    // 1. SafeFilePath validates the path (correct extension, no path traversal, file exists)
-    // 2. File::open gives us the file handle
-    // 3. BufReader wraps it to provide buffered reading (implements BufRead trait)
-    //
-    // The parser's parse() method takes anything that implements BufRead, which is why
-    // we need the BufReader wrapper. This design keeps the parser flexible: it can work
-    // with files, strings wrapped in Cursor, network streams, or any BufRead source.
+    // Any arguments select a command; see cli.rs for the list.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        // Printed with Display rather than the Debug output `main` would give.
+        if let Err(e) = cli::run(&args) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // parse_file() validates the path through SafeFilePath (correct extension, no path
+    // traversal, file exists), opens the file and wraps it in a BufReader. The returned
+    // iterator owns the file handle.
     //
-    // Note: SubtitleParser has stubbed convenience methods parse_file() and parse_str()
-    // that will handle this setup internally once implemented. You can give it a shot!
+    // The underlying parse() method takes anything that implements BufRead, which keeps
+    // the parser flexible: it can work with files, strings (see parse_str()), network
+    // streams, or any BufRead source.
     let filepath = "data/raw/psycho-pass-s01e01-jp.srt";
-    let checked_path_result = SafeFilePath::try_from(filepath)?;
-    let file = File::open(checked_path_result)?;
-    let reader = BufReader::new(file);
 
     let mut parser = SubtitleParser::new();
 
     // The parser takes a BufRead and returns an iterator over Result<SubtitleUnit, Error>.
     // This is the lazy approach - parse units one at a time rather than loading everything
-    // into memory. Once you implement the parse method, you can iterate like this:
-    //
-    // for unit_result in parser.parse(reader)? {
-    //     match unit_result {
-    //         Ok(subtitle_unit) => println!("{:?}", subtitle_unit),
-    //         Err(e) => eprintln!("Error: {}", e),
-    //     }
-    // }
+    // into memory. In the default strict mode, the first malformed unit comes through as
+    // an error and ends the iteration; see ParseMode for the alternatives.
     //
-    // Or collect eagerly: let units: Result<Vec<_>, _> = parser.parse(reader)?.collect();
+    // Or collect eagerly: let units: Vec<SubtitleUnit> = parser.read_file(filepath)?;
     //
     // When we expose this to Python via PyO3, the iterator maps nicely to Python
     // generators. You'll use #[pyclass] and implement __iter__/__next__ so Python code
     // can do: for unit in subtitle_parser.parse(content): ...
     // And of course it also will work with a Polars DataFrame, because we can
     // convert the Vec<SubtitleUnit> into a DataFrame easily or even stream rows.
+    for unit_result in parser.parse_file(filepath)? {
+        match unit_result {
+            Ok(subtitle_unit) => println!("{:?}", subtitle_unit),
+            Err(e) => eprint!("{}", render_diagnostic(&e)),
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,176 @@
+use crate::types::timestamp::{Timestamp, TimestampError};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A video framerate as an exact fraction of frames per second, so NTSC rates
+/// like 24000/1001 don’t pick up floating-point error.
+///
+/// Parses from `25`, `24000/1001` or a decimal such as `23.976`. The usual
+/// rounded NTSC spellings (`23.976`, `29.97`, `59.94` and longer variants) are
+/// read as their exact /1001 fractions; any other decimal is taken literally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framerate {
+    numerator: u32,
+    denominator: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FramerateError {
+    ZeroFramerate,
+    MalformedFramerate(String),
+}
+
+impl Framerate {
+    /// NTSC film, 23.976 fps.
+    pub const NTSC_FILM: Framerate = Framerate {
+        numerator: 24_000,
+        denominator: 1_001,
+    };
+    pub const FILM: Framerate = Framerate {
+        numerator: 24,
+        denominator: 1,
+    };
+    pub const PAL: Framerate = Framerate {
+        numerator: 25,
+        denominator: 1,
+    };
+    /// NTSC video, 29.97 fps.
+    pub const NTSC: Framerate = Framerate {
+        numerator: 30_000,
+        denominator: 1_001,
+    };
+
+    /// The framerate `numerator`/`denominator`, in lowest terms.
+    pub fn new(numerator: u32, denominator: u32) -> Result<Self, FramerateError> {
+        if numerator == 0 || denominator == 0 {
+            return Err(FramerateError::ZeroFramerate);
+        }
+        let divisor = gcd(numerator, denominator);
+        Ok(Framerate {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        })
+    }
+
+    pub fn numerator(&self) -> u32 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> u32 {
+        self.denominator
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    /// When frame `frame` (counting from 0) starts, to the nearest millisecond.
+    /// Fails with [`TimestampError::Overflow`] beyond [`Timestamp::MAX`].
+    pub fn frame_to_timestamp(self, frame: u64) -> Result<Timestamp, TimestampError> {
+        let millis = div_round(
+            frame as i128 * 1_000 * self.denominator as i128,
+            self.numerator as i128,
+        );
+        Timestamp::from_millis(u64::try_from(millis).map_err(|_| TimestampError::Overflow)?)
+    }
+
+    /// The frame showing at `timestamp`, rounded to the nearest frame start.
+    pub fn timestamp_to_frame(self, timestamp: Timestamp) -> u64 {
+        let frame = div_round(
+            timestamp.to_millis() as i128 * self.numerator as i128,
+            1_000 * self.denominator as i128,
+        );
+        // At most 255 hours at under 2^32 fps, so this fits in a u64.
+        frame as u64
+    }
+}
+
+/// Integer division rounding half away from zero.
+pub(crate) fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+
+    if remainder.abs() * 2 >= denominator.abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Display for Framerate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl FromStr for Framerate {
+    type Err = FramerateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || FramerateError::MalformedFramerate(s.to_string());
+        let parse_u32 = |raw: &str| raw.parse::<u32>().map_err(|_| malformed());
+
+        if let Some((numerator, denominator)) = s.split_once('/') {
+            return Framerate::new(parse_u32(numerator)?, parse_u32(denominator)?);
+        }
+
+        let Some((whole, fraction)) = s.split_once('.') else {
+            return Framerate::new(parse_u32(s)?, 1);
+        };
+
+        // 23.976, 23.9760, 29.97, 59.94, ... are all rounded NTSC rates.
+        for ntsc_base in [24_000, 30_000, 48_000, 60_000] {
+            let exact = ntsc_base as f64 / 1_001.0;
+            let rounded = format!("{:.*}", fraction.len(), exact);
+            if fraction.len() >= 2 && rounded == s {
+                return Framerate::new(ntsc_base, 1_001);
+            }
+        }
+
+        if fraction.is_empty() || fraction.len() > 6 {
+            return Err(malformed());
+        }
+        let denominator = 10u32.pow(fraction.len() as u32);
+        let numerator = parse_u32(whole)?
+            .checked_mul(denominator)
+            .and_then(|n| n.checked_add(parse_u32(fraction).ok()?))
+            .ok_or_else(malformed)?;
+        Framerate::new(numerator, denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_framerate() {
+        assert_eq!("29.97".parse::<Framerate>().unwrap(), Framerate::NTSC);
+        assert_eq!(
+            "24000/1001".parse::<Framerate>().unwrap(),
+            Framerate::NTSC_FILM
+        );
+        assert_eq!("25".parse::<Framerate>().unwrap(), Framerate::PAL);
+        assert_eq!("23.5".parse::<Framerate>().unwrap().to_string(), "47/2");
+        assert_eq!("50/2".parse::<Framerate>().unwrap(), Framerate::PAL);
+        assert!("0".parse::<Framerate>().is_err());
+    }
+
+    #[test]
+    fn test_div_round_rounds_halves_away_from_zero() {
+        assert_eq!(div_round(5, 2), 3);
+        assert_eq!(div_round(-5, 2), -3);
+        assert_eq!(div_round(4, 3), 1);
+    }
+}
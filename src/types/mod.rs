@@ -1,4 +1,6 @@
+pub mod framerate;
+pub mod segment;
 pub mod srt_index;
+pub mod subtitle_unit;
 pub mod timestamp;
 pub mod timing;
-pub mod subtitle_unit;
\ No newline at end of file
@@ -0,0 +1,387 @@
+/// One piece of structure inside the lines of a [`SubtitleUnit`], as Japanese
+/// closed captions write it. A unit’s lines become a flat list of segments,
+/// with [`Segment::LineBreak`] between lines.
+///
+/// ```text
+/// （狡噛(こうがみ)）お前は 槙島 聖護(しょうご)だ―
+/// ```
+///
+/// is a [`Speaker`](Segment::Speaker) holding the ruby 狡噛/こうがみ, then the
+/// text `お前は 槙島 `, the ruby 聖護/しょうご, the text `だ` and a
+/// [`Continuation`](Segment::Continuation).
+///
+/// [`SubtitleUnit`]: crate::types::subtitle_unit::SubtitleUnit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// A full-width parenthesised label naming who speaks, e.g. `（狡噛）`. The
+    /// label’s content is kept as [`Text`](Segment::Text) and
+    /// [`Ruby`](Segment::Ruby) segments, since names often carry furigana.
+    Speaker(Vec<Segment>),
+    /// Furigana: `base(reading)`, where `base` is the run of kanji (or of
+    /// full-width letters, as in `ＫＴ(ケーティー)`) before a half-width
+    /// parenthesis holding kana.
+    Ruby {
+        base: String,
+        reading: String,
+    },
+    /// A parenthesised description of a sound, e.g. `（足音）`.
+    SoundEffect(Vec<Segment>),
+    /// A music cue, `♪～` or `♪ lyrics ♪`, holding the lyrics (often empty).
+    Music(String),
+    Text(String),
+    /// The break between two lines of a unit.
+    LineBreak,
+    /// A `―` ending a line, meaning the sentence carries on in the next unit.
+    Continuation,
+}
+
+impl Segment {
+    /// The text shown on the main line: `Text` as is and `Ruby` by its base.
+    /// Empty for everything else.
+    pub fn base_text(&self) -> &str {
+        match self {
+            Segment::Text(text) => text,
+            Segment::Ruby { base, .. } => base,
+            _ => "",
+        }
+    }
+}
+
+pub fn parse_segments(lines: &[String]) -> Vec<Segment> {
+    //! Splits the lines of a unit into segments.
+    //!
+    //! A full-width parenthesised label at the start of a line is a speaker if
+    //! dialogue follows it, either on the same line or on a later line of the
+    //! unit. Otherwise (a label on the unit’s last line with nothing after it,
+    //! or a label mid-line) it is a sound effect. A half-width parenthesis that
+    //! isn’t furigana is also a sound effect.
+    //!
+    //! An unclosed parenthesis is kept as text, along with everything after it.
+
+    let mut segments = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            segments.push(Segment::LineBreak);
+        }
+        let is_last_line = i + 1 == lines.len();
+        parse_line(line, is_last_line, &mut segments);
+    }
+
+    segments
+}
+
+pub fn spoken_text(segments: &[Segment]) -> String {
+    //! The dialogue alone: text and ruby bases, with lines joined by `\n`.
+    //! Speakers, sound effects, music and continuation dashes are left out.
+
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::LineBreak => "\n",
+            other => other.base_text(),
+        })
+        .collect()
+}
+
+pub fn render_segments(segments: &[Segment]) -> String {
+    //! Turns segments back into subtitle text, with lines joined by `\n`.
+    //!
+    //! Labels always come out in full-width parentheses and music as `♪～` or
+    //! `♪ lyrics ♪`, so this undoes [`parse_segments`] exactly only for text
+    //! written that way, which is how Japanese closed captions usually are.
+
+    let mut text = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Speaker(inner) | Segment::SoundEffect(inner) => {
+                text.push('（');
+                text.push_str(&render_segments(inner));
+                text.push('）');
+            }
+            Segment::Ruby { base, reading } => {
+                text.push_str(&format!("{}({})", base, reading));
+            }
+            Segment::Music(lyrics) if lyrics.is_empty() => text.push_str("♪～"),
+            Segment::Music(lyrics) => text.push_str(&format!("♪ {} ♪", lyrics)),
+            Segment::Text(plain) => text.push_str(plain),
+            Segment::LineBreak => text.push('\n'),
+            Segment::Continuation => text.push('―'),
+        }
+    }
+    text
+}
+
+fn parse_line(line: &str, is_last_line: bool, segments: &mut Vec<Segment>) {
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '（' => {
+                let Some(close) = closing_parenthesis(&chars, i) else {
+                    push_text(segments, &chars[i..].iter().collect::<String>());
+                    break;
+                };
+                let inner = parse_inline(&chars[i + 1..close]);
+                let at_line_start = chars[..i].iter().all(|c| c.is_whitespace());
+                let followed = chars[close + 1..].iter().any(|c| !c.is_whitespace());
+
+                if at_line_start && (followed || !is_last_line) {
+                    segments.push(Segment::Speaker(inner));
+                } else {
+                    segments.push(Segment::SoundEffect(inner));
+                }
+                i = close + 1;
+            }
+            '(' => {
+                let Some(close) = closing_parenthesis(&chars, i) else {
+                    push_text(segments, &chars[i..].iter().collect::<String>());
+                    break;
+                };
+                let inner: String = chars[i + 1..close].iter().collect();
+                if !attach_ruby(segments, &inner) {
+                    segments.push(Segment::SoundEffect(parse_inline(&chars[i + 1..close])));
+                }
+                i = close + 1;
+            }
+            '♪' => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '♪')
+                    .map_or(chars.len(), |offset| i + 1 + offset);
+                let lyrics: String = chars[i + 1..close].iter().collect();
+                segments.push(Segment::Music(
+                    lyrics
+                        .trim_matches(|c: char| c == '～' || c.is_whitespace())
+                        .to_string(),
+                ));
+                // Skip the closing ♪ and a trailing ～ after it.
+                i = close + 1;
+                while chars.get(i) == Some(&'～') {
+                    i += 1;
+                }
+            }
+            '―' if chars[i + 1..].iter().all(|c| *c == '―') => {
+                segments.push(Segment::Continuation);
+                break;
+            }
+            c => {
+                push_text(segments, &c.to_string());
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Text and ruby only, for the inside of speaker and sound-effect labels.
+fn parse_inline(chars: &[char]) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '('
+            && let Some(close) = closing_parenthesis(chars, i)
+        {
+            let inner: String = chars[i + 1..close].iter().collect();
+            if attach_ruby(&mut segments, &inner) {
+                i = close + 1;
+                continue;
+            }
+        }
+        push_text(&mut segments, &chars[i].to_string());
+        i += 1;
+    }
+
+    segments
+}
+
+/// The index of the parenthesis closing the one at `open`, counting both
+/// half- and full-width parentheses as nesting.
+fn closing_parenthesis(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '(' | '（' => depth += 1,
+            ')' | '）' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// If `reading` is all kana and the text so far ends in kanji or full-width
+/// letters, moves that run into a new [`Segment::Ruby`] and returns `true`.
+fn attach_ruby(segments: &mut Vec<Segment>, reading: &str) -> bool {
+    if reading.is_empty() || !reading.chars().all(is_kana) {
+        return false;
+    }
+    let Some(Segment::Text(text)) = segments.last_mut() else {
+        return false;
+    };
+
+    let base_start = text
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_ruby_base(c))
+        .last()
+        .map(|(i, _)| i);
+    let Some(base_start) = base_start else {
+        return false;
+    };
+
+    let base = text.split_off(base_start);
+    if text.is_empty() {
+        segments.pop();
+    }
+    segments.push(Segment::Ruby {
+        base,
+        reading: reading.to_string(),
+    });
+    true
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &str) {
+    match segments.last_mut() {
+        Some(Segment::Text(previous)) => previous.push_str(text),
+        _ => segments.push(Segment::Text(text.to_string())),
+    }
+}
+
+fn is_ruby_base(c: char) -> bool {
+    is_kanji(c) || matches!(c, 'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '０'..='９')
+}
+
+/// Kanji: CJK ideographs, and the marks that stand in for them (`々`, `〻`, `〆`
+/// and `〇`), which take furigana too. This is the one definition of kanji that
+/// segments, script classes and kanji lists all share.
+pub(crate) fn is_kanji(c: char) -> bool {
+    is_ideograph(c) || matches!(c, '々' | '〻' | '〆' | '〇')
+}
+
+/// CJK ideographs alone: the unified block, Extensions A to G, and the
+/// compatibility blocks.
+pub(crate) fn is_ideograph(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{20000}'..='\u{3134F}'
+        | '\u{F900}'..='\u{FAFF}')
+}
+
+/// Hiragana and katakana, full- or half-width, with the prolonged sound mark.
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataprep::parser::SubtitleParser;
+
+    fn segments(lines: &[&str]) -> Vec<Segment> {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        parse_segments(&lines)
+    }
+
+    fn text(s: &str) -> Segment {
+        Segment::Text(s.to_string())
+    }
+
+    fn ruby(base: &str, reading: &str) -> Segment {
+        Segment::Ruby {
+            base: base.to_string(),
+            reading: reading.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_speakers_ruby_and_continuations() {
+        assert_eq!(
+            segments(&["（槙島(まきしま)）お前は 狡噛 慎也(しんや)だ―"]),
+            vec![
+                Segment::Speaker(vec![ruby("槙島", "まきしま")]),
+                text("お前は 狡噛 "),
+                ruby("慎也", "しんや"),
+                text("だ"),
+                Segment::Continuation,
+            ]
+        );
+        // A label alone on a line still names the speaker of the next line.
+        assert_eq!(
+            segments(&["（コミッサ）", "こちらは 公安局刑事課です―"]),
+            vec![
+                Segment::Speaker(vec![text("コミッサ")]),
+                Segment::LineBreak,
+                text("こちらは 公安局刑事課です"),
+                Segment::Continuation,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_width_letters_take_furigana() {
+        assert_eq!(
+            segments(&["ＫＴ(ケーティー)の２号(にごう)"]),
+            vec![
+                ruby("ＫＴ", "ケーティー"),
+                text("の"),
+                ruby("２号", "にごう")
+            ]
+        );
+        // Half-width letters don’t, so their parenthesis is a sound effect.
+        assert_eq!(
+            segments(&["KT(ケーティー)"]),
+            vec![text("KT"), Segment::SoundEffect(vec![text("ケーティー")])]
+        );
+    }
+
+    #[test]
+    fn test_sound_effects_and_music() {
+        assert_eq!(
+            segments(&["（足音）"]),
+            vec![Segment::SoundEffect(vec![text("足音")])]
+        );
+        assert_eq!(segments(&["♪～"]), vec![Segment::Music(String::new())]);
+        assert_eq!(
+            segments(&["♪ 名前のない怪物 ♪ ハァ…"]),
+            vec![Segment::Music("名前のない怪物".to_string()), text(" ハァ…")]
+        );
+        // Kana in parentheses after more kana isn’t furigana.
+        assert_eq!(
+            segments(&["えっと(ささやき)"]),
+            vec![text("えっと"), Segment::SoundEffect(vec![text("ささやき")])]
+        );
+    }
+
+    #[test]
+    fn test_episode_has_no_stray_parentheses() {
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+
+        for unit in &units {
+            let spoken = spoken_text(&unit.segments());
+            assert!(!spoken.contains(['(', ')', '（', '）', '♪']), "{}", unit);
+        }
+        assert_eq!(spoken_text(&units[1].segments()), "フゥ～…");
+    }
+
+    #[test]
+    fn test_render_segments_undoes_parsing() {
+        let lines = ["（槙島(まきしま)）（足音）", "♪ 名前のない怪物 ♪ 待て―"];
+        assert_eq!(render_segments(&segments(&lines)), lines.join("\n"));
+
+        let units = SubtitleParser::new()
+            .read_file("data/raw/psycho-pass-s01e01-jp.srt")
+            .unwrap();
+        for unit in &units {
+            assert_eq!(render_segments(&unit.segments()), unit.lines.join("\n"));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 const PERMITTED_INDEX_CHARS: &str = "0123456789";
@@ -12,6 +13,24 @@ const U32_MAX_4294967295: usize = u32::MAX as usize;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SrtIndex(u32);
 
+impl SrtIndex {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for SrtIndex {
+    fn from(value: u32) -> Self {
+        SrtIndex(value)
+    }
+}
+
+impl Display for SrtIndex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Debug)]
 pub enum SrtIndexError {
     EmptyIndex,
@@ -25,7 +44,7 @@ impl FromStr for SrtIndex {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.is_empty() {
             return Err(SrtIndexError::EmptyIndex);
-        } else if s.chars().all(|char| PERMITTED_INDEX_CHARS.contains(char)) == false {
+        } else if !s.chars().all(|char| PERMITTED_INDEX_CHARS.contains(char)) {
             return Err(SrtIndexError::IndexContainsDisallowedChars);
         }
 
@@ -1,5 +1,7 @@
+use crate::types::segment::{Segment, parse_segments};
 use crate::types::srt_index::SrtIndex;
 use crate::types::timing::Timing;
+use std::fmt::{Display, Formatter};
 
 /// A `SubtitleUnit` represents one complete group of lines in an SRT file,
 /// where groups of lines are separated by blank lines in well-formed SRT
@@ -21,4 +23,25 @@ pub struct SubtitleUnit {
     pub index: SrtIndex,
     pub timing: Timing,
     pub lines: Vec<String>
-}
\ No newline at end of file
+}
+
+impl SubtitleUnit {
+    /// The unit’s lines split into speakers, furigana, sound effects, music
+    /// and plain text. See [`parse_segments`].
+    pub fn segments(&self) -> Vec<Segment> {
+        parse_segments(&self.lines)
+    }
+}
+
+impl Display for SubtitleUnit {
+    /// Writes the unit as an SRT block with `\n` line endings and no trailing
+    /// newline. Use [`SrtWriter`](crate::dataprep::writer::SrtWriter) to write
+    /// whole files.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n{}", self.index, self.timing)?;
+        for line in &self.lines {
+            write!(f, "\n{}", line)?;
+        }
+        Ok(())
+    }
+}
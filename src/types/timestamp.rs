@@ -1,12 +1,23 @@
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Sub};
 use std::str::FromStr;
+use std::time::Duration;
 
 const PERMITTED_TIMESTAMP_CHARS: &str = "0123456789:,";
 const U8_MAX_255: usize = u8::MAX as usize;
 const U16_MAX_65535: usize = u16::MAX as usize;
 
+const MILLIS_PER_SECOND: u64 = 1_000;
+const MILLIS_PER_MINUTE: u64 = 60 * MILLIS_PER_SECOND;
+const MILLIS_PER_HOUR: u64 = 60 * MILLIS_PER_MINUTE;
+
 ///Rich timestamp representation.
 ///Represents `hh:mm:ss,mmm`.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+///
+///The field order matters: the derived `Ord` compares hours first, then
+///minutes and so on, which is chronological order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Timestamp {
     pub hours: u8,
     pub minutes: u8,
@@ -18,6 +29,10 @@ pub struct Timestamp {
 pub enum TimestampError {
     EmptyString,
     MalformedTimestamp(String),
+    /// Arithmetic would go below `00:00:00,000`.
+    Underflow,
+    /// Arithmetic would go beyond [`Timestamp::MAX`].
+    Overflow,
 }
 
 impl TimestampError {
@@ -26,6 +41,143 @@ impl TimestampError {
     }
 }
 
+impl Timestamp {
+    pub const ZERO: Timestamp = Timestamp {
+        hours: 0,
+        minutes: 0,
+        seconds: 0,
+        milliseconds: 0,
+    };
+
+    /// `255:59:59,999`, the latest time the `u8` hours field can hold.
+    pub const MAX: Timestamp = Timestamp {
+        hours: u8::MAX,
+        minutes: 59,
+        seconds: 59,
+        milliseconds: 999,
+    };
+
+    pub fn to_millis(self) -> u64 {
+        self.hours as u64 * MILLIS_PER_HOUR
+            + self.minutes as u64 * MILLIS_PER_MINUTE
+            + self.seconds as u64 * MILLIS_PER_SECOND
+            + self.milliseconds as u64
+    }
+
+    /// Fails with [`TimestampError::Overflow`] beyond [`Timestamp::MAX`].
+    pub fn from_millis(total: u64) -> Result<Self, TimestampError> {
+        if total > Timestamp::MAX.to_millis() {
+            return Err(TimestampError::Overflow);
+        }
+
+        Ok(Timestamp {
+            hours: (total / MILLIS_PER_HOUR) as u8,
+            minutes: (total % MILLIS_PER_HOUR / MILLIS_PER_MINUTE) as u8,
+            seconds: (total % MILLIS_PER_MINUTE / MILLIS_PER_SECOND) as u8,
+            milliseconds: (total % MILLIS_PER_SECOND) as u16,
+        })
+    }
+
+    pub fn to_duration(self) -> Duration {
+        Duration::from_millis(self.to_millis())
+    }
+
+    /// Sub-millisecond precision is truncated. Fails with
+    /// [`TimestampError::Overflow`] beyond [`Timestamp::MAX`].
+    pub fn from_duration(duration: Duration) -> Result<Self, TimestampError> {
+        let total = u64::try_from(duration.as_millis()).map_err(|_| TimestampError::Overflow)?;
+        Timestamp::from_millis(total)
+    }
+
+    pub fn checked_add(self, duration: Duration) -> Result<Self, TimestampError> {
+        let delta = Timestamp::from_duration(duration)?.to_millis();
+        Timestamp::from_millis(self.to_millis() + delta)
+    }
+
+    pub fn checked_sub(self, duration: Duration) -> Result<Self, TimestampError> {
+        let total = self
+            .to_millis()
+            .checked_sub(duration_millis_saturating(duration))
+            .ok_or(TimestampError::Underflow)?;
+        Timestamp::from_millis(total)
+    }
+
+    /// Clamps at [`Timestamp::MAX`].
+    pub fn saturating_add(self, duration: Duration) -> Self {
+        self.checked_add(duration).unwrap_or(Timestamp::MAX)
+    }
+
+    /// Clamps at [`Timestamp::ZERO`].
+    pub fn saturating_sub(self, duration: Duration) -> Self {
+        self.checked_sub(duration).unwrap_or(Timestamp::ZERO)
+    }
+
+    /// Time elapsed from `earlier` to `self`; `None` if `earlier` is later.
+    pub fn checked_duration_since(self, earlier: Timestamp) -> Option<Duration> {
+        let millis = self.to_millis().checked_sub(earlier.to_millis())?;
+        Some(Duration::from_millis(millis))
+    }
+}
+
+fn duration_millis_saturating(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+impl From<Timestamp> for Duration {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_duration()
+    }
+}
+
+impl TryFrom<Duration> for Timestamp {
+    type Error = TimestampError;
+
+    fn try_from(duration: Duration) -> Result<Self, Self::Error> {
+        Timestamp::from_duration(duration)
+    }
+}
+
+impl Add<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    /// Panics beyond [`Timestamp::MAX`], like integer overflow does in debug
+    /// builds. Use [`Timestamp::checked_add`] or [`Timestamp::saturating_add`]
+    /// to handle it instead.
+    fn add(self, duration: Duration) -> Self::Output {
+        self.checked_add(duration)
+            .expect("Timestamp overflow when adding duration")
+    }
+}
+
+impl Sub<Duration> for Timestamp {
+    type Output = Timestamp;
+
+    /// Panics below zero. Use [`Timestamp::checked_sub`] or
+    /// [`Timestamp::saturating_sub`] to handle it instead.
+    fn sub(self, duration: Duration) -> Self::Output {
+        self.checked_sub(duration)
+            .expect("Timestamp underflow when subtracting duration")
+    }
+}
+
+impl Display for Timestamp {
+    /// Writes the SRT form, `hh:mm:ss,mmm`, zero-padded.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02},{:03}",
+            self.hours, self.minutes, self.seconds, self.milliseconds
+        )
+    }
+}
+
+impl Serialize for Timestamp {
+    /// Serialises as the SRT form, the same as [`Display`].
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 impl FromStr for Timestamp {
     type Err = TimestampError;
 
@@ -47,10 +199,9 @@ impl FromStr for Timestamp {
                 "Timestamp string cannot contain newlines",
                 s,
             ));
-        } else if s
+        } else if !s
             .chars()
             .all(|char| PERMITTED_TIMESTAMP_CHARS.contains(char))
-            == false
         {
             return Err(TimestampError::malformed(
                 "Illegal characters detected; allowed characters are 0123456789:,",
@@ -101,7 +252,9 @@ impl FromStr for Timestamp {
             ));
         }
 
-        let hours = raw_hh.parse::<usize>().unwrap();
+        // Every field is all digits by now, so parsing can only fail on overflow,
+        // which the range checks below then reject.
+        let hours = raw_hh.parse::<usize>().unwrap_or(usize::MAX);
         if hours > U8_MAX_255 {
             // Should I increase the allocation to u16?
             return Err(TimestampError::malformed(
@@ -109,7 +262,7 @@ impl FromStr for Timestamp {
                 s,
             ));
         }
-        let minutes = raw_mm.parse::<usize>().unwrap();
+        let minutes = raw_mm.parse::<usize>().unwrap_or(usize::MAX);
         if minutes > U8_MAX_255 {
             return Err(TimestampError::malformed(
                 "Minutes value exceeds maximum unsigned 8-bit value of 255",
@@ -118,7 +271,7 @@ impl FromStr for Timestamp {
         } else if minutes > 59 {
             return Err(TimestampError::malformed("Minutes value exceeds 59", s));
         }
-        let seconds = raw_ss.parse::<usize>().unwrap();
+        let seconds = raw_ss.parse::<usize>().unwrap_or(usize::MAX);
         if seconds > U8_MAX_255 {
             return Err(TimestampError::malformed(
                 "Seconds value exceeds maximum unsigned 8-bit value of 255",
@@ -127,9 +280,8 @@ impl FromStr for Timestamp {
         } else if seconds > 59 {
             return Err(TimestampError::malformed("Seconds value exceeds 59", s));
         }
-        let milliseconds = raw_ms.parse::<usize>().unwrap();
+        let milliseconds = raw_ms.parse::<usize>().unwrap_or(usize::MAX);
         if milliseconds > U16_MAX_65535 {
-            println!("Regarding timestamp string: {}", s);
             return Err(TimestampError::malformed(
                 "Milliseconds value exceeds maximum unsigned 16-bit value of 65535",
                 s,
@@ -149,3 +301,66 @@ impl FromStr for Timestamp {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_millis_round_trip() {
+        let timestamp = "01:02:03,456".parse::<Timestamp>().unwrap();
+
+        assert_eq!(timestamp.to_millis(), 3_723_456);
+        assert_eq!(Timestamp::from_millis(3_723_456).unwrap(), timestamp);
+        assert_eq!(Duration::from(timestamp), Duration::from_millis(3_723_456));
+    }
+
+    #[test]
+    fn test_add_and_sub_durations() {
+        let timestamp = "00:00:59,900".parse::<Timestamp>().unwrap();
+
+        assert_eq!(
+            (timestamp + Duration::from_millis(200)).to_string(),
+            "00:01:00,100"
+        );
+        assert_eq!(
+            (timestamp - Duration::from_secs(59)).to_string(),
+            "00:00:00,900"
+        );
+    }
+
+    #[test]
+    fn test_overlong_fields_are_errors() {
+        for input in [
+            "99999999999999999999999:00:01,000",
+            "00:00:01,99999999999999999999999",
+        ] {
+            assert!(matches!(
+                input.parse::<Timestamp>(),
+                Err(TimestampError::MalformedTimestamp(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_checked_and_saturating_bounds() {
+        let timestamp = "00:00:01,000".parse::<Timestamp>().unwrap();
+
+        assert_eq!(
+            timestamp.checked_sub(Duration::from_secs(2)),
+            Err(TimestampError::Underflow)
+        );
+        assert_eq!(
+            timestamp.saturating_sub(Duration::from_secs(2)),
+            Timestamp::ZERO
+        );
+        assert_eq!(
+            Timestamp::MAX.checked_add(Duration::from_millis(1)),
+            Err(TimestampError::Overflow)
+        );
+        assert_eq!(
+            Timestamp::MAX.saturating_add(Duration::from_secs(1)),
+            Timestamp::MAX
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::types::timestamp;
 use crate::types::timestamp::{Timestamp, TimestampError};
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
+use std::time::Duration;
 
 const TIMING_SEPARATOR: &str = "-->";
 
@@ -9,7 +11,7 @@ const TIMING_SEPARATOR: &str = "-->";
 /// appears on the screen and another to indicate when it disappears.
 ///
 /// A `Timing` separator looks as follows: `-->`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Timing {
     pub start: Timestamp,
     pub end: Timestamp,
@@ -37,32 +39,81 @@ impl From<TimestampError> for TimingError {
     }
 }
 
-impl FromStr for Timing {
-    type Err = TimingError;
+impl Timing {
+    /// How long the subtitle is on screen. Zero if `end` is before `start`,
+    /// which can only happen if the fields were set directly.
+    pub fn duration(&self) -> Duration {
+        self.end
+            .checked_duration_since(self.start)
+            .unwrap_or(Duration::ZERO)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.is_empty() {
-            return Err(TimingError::EmptyTiming);
+    /// Parses a timing line in the same way as [`Timing::from_str`], except that
+    /// a start timestamp later than the end timestamp is accepted and the two are
+    /// swapped. The returned flag is `true` when a swap took place.
+    pub fn parse_swapping_reversed(s: &str) -> Result<(Timing, bool), TimingError> {
+        let (start_timestamp, end_timestamp) = split_timestamps(s)?;
+
+        if start_timestamp > end_timestamp {
+            Ok((
+                Timing {
+                    start: end_timestamp,
+                    end: start_timestamp,
+                },
+                true,
+            ))
+        } else {
+            Ok((
+                Timing {
+                    start: start_timestamp,
+                    end: end_timestamp,
+                },
+                false,
+            ))
         }
+    }
+}
+
+/// Splits a timing line on [`TIMING_SEPARATOR`] and parses both sides, without
+/// checking the order of the two timestamps.
+fn split_timestamps(s: &str) -> Result<(Timestamp, Timestamp), TimingError> {
+    if s.is_empty() {
+        return Err(TimingError::EmptyTiming);
+    }
 
-        let split_s = s.split(TIMING_SEPARATOR);  // `split_s` is an iterator
-        let split_s_collected: Vec<&str> = split_s.collect();
-        let split_s_elems = split_s_collected.len();
+    let split_s = s.split(TIMING_SEPARATOR);  // `split_s` is an iterator
+    let split_s_collected: Vec<&str> = split_s.collect();
+    let split_s_elems = split_s_collected.len();
+
+    if split_s_elems == 1 {
+        return Err(TimingError::malformed(
+            "Missing timestamp separator (-->)",
+            s,
+        ));
+    } else if split_s_elems > 2 {
+        return Err(TimingError::malformed("Multiple timestamp separators", s));
+    }
 
-        if split_s_elems == 1 {
-            return Err(TimingError::malformed(
-                "Missing timestamp separator (-->)",
-                s,
-            ));
-        } else if split_s_elems > 2 {
-            return Err(TimingError::malformed("Multiple timestamp separators", s));
-        }
+    let start_raw: &str = split_s_collected[0].trim();
+    let start_timestamp = start_raw.parse::<Timestamp>()?;
 
-        let start_raw: &str = split_s_collected[0].trim();
-        let start_timestamp = start_raw.parse::<Timestamp>()?;
+    let end_raw: &str = split_s_collected[1].trim();
+    let end_timestamp = end_raw.parse::<Timestamp>()?;
 
-        let end_raw: &str = split_s_collected[1].trim();
-        let end_timestamp = end_raw.parse::<Timestamp>()?;
+    Ok((start_timestamp, end_timestamp))
+}
+
+impl Display for Timing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.start, TIMING_SEPARATOR, self.end)
+    }
+}
+
+impl FromStr for Timing {
+    type Err = TimingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start_timestamp, end_timestamp) = split_timestamps(s)?;
 
         if start_timestamp > end_timestamp {
             return Err(TimingError::malformed(
@@ -112,4 +163,28 @@ mod tests {
             _ => panic!("Expected this error message: Missing timestamp separator (-->)"),
         }
     }
+
+    #[test]
+    fn test_display_round_trips() {
+        let input = "00:00:07,005 --> 01:18:27,439";
+        let timing = input.parse::<Timing>().unwrap();
+
+        assert_eq!(timing.to_string(), input);
+    }
+
+    #[test]
+    fn test_duration() {
+        let timing = "00:18:25,437 --> 00:18:27,439".parse::<Timing>().unwrap();
+        assert_eq!(timing.duration(), Duration::from_millis(2_002));
+    }
+
+    #[test]
+    fn test_parse_swapping_reversed() {
+        let input = "00:18:27,439 --> 00:18:25,437";
+        let (timing, swapped) = Timing::parse_swapping_reversed(input).unwrap();
+
+        assert!(swapped);
+        assert!(timing.start < timing.end);
+        assert!(input.parse::<Timing>().is_err());
+    }
 }